@@ -0,0 +1,297 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    bundle::Bundle,
+    component::{Component, ComponentStorage, Entity},
+};
+
+// Total time spent in each loop over every iteration
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BenchReport {
+    pub entities: usize,
+    pub iterations: usize,
+    pub update: Duration,
+    pub query: Duration,
+    pub get: Duration,
+}
+
+pub struct BenchHarness {
+    storage: ComponentStorage,
+    entities: Vec<Entity>,
+}
+
+impl BenchHarness {
+    pub fn new<B: Bundle>(count: usize, factory: impl FnMut(usize) -> B) -> Self {
+        let mut storage = ComponentStorage::new();
+        let entities = storage.spawn_batch(count, factory);
+        storage.setup_components();
+        Self { storage, entities }
+    }
+
+    pub fn storage(&self) -> &ComponentStorage {
+        &self.storage
+    }
+
+    // For shaping the world before a run, the entities handed out by `new` aren't tracked again
+    pub fn storage_mut(&mut self) -> &mut ComponentStorage {
+        &mut self.storage
+    }
+
+    // `T` is the component looked up by the query and get loops
+    pub fn run<T: Component + 'static>(&mut self, iterations: usize) -> BenchReport {
+        let mut report = BenchReport {
+            entities: self.entities.len(),
+            iterations,
+            ..Default::default()
+        };
+
+        for _ in 0..iterations {
+            let start = Instant::now();
+            self.storage.update_components();
+            report.update += start.elapsed();
+
+            let start = Instant::now();
+            let matched = self.storage.entities().with::<T>().iter().count();
+            std::hint::black_box(matched);
+            report.query += start.elapsed();
+
+            let start = Instant::now();
+            for entity in self.entities.iter() {
+                std::hint::black_box(self.storage.get_entity_component::<T>(entity));
+            }
+            report.get += start.elapsed();
+        }
+        report
+    }
+}
+
+// Run with `cargo test --release --features bench -- --nocapture` for the printed timings
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Counter, Tag, Transform, Velocity};
+
+    // Shortest of several rounds, the least noisy figure for a short loop
+    fn fastest(rounds: usize, mut f: impl FnMut()) -> Duration {
+        (0..rounds)
+            .map(|_| {
+                let start = Instant::now();
+                f();
+                start.elapsed()
+            })
+            .min()
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn repeated_same_type_lookups_hit_the_cached_column() {
+        let mut harness = BenchHarness::new(1000, |_| (Tag::<0>,));
+        let storage = harness.storage_mut();
+        macro_rules! ensure_columns {
+            ($($n:literal),*) => { $(storage.ensure_column::<Tag<$n>>();)* };
+        }
+        ensure_columns!(1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19);
+        assert_eq!(storage.component_vectors.len(), 20);
+
+        let entities = harness.storage().get_entities();
+        let storage = harness.storage();
+        let same_type = fastest(10, || {
+            for entity in entities.iter() {
+                std::hint::black_box(storage.get_entity_component::<Tag<19>>(entity));
+            }
+        });
+        assert_eq!(storage.last_accessed_column.get(), 19);
+        // Every lookup misses the cache, `Tag<19>` is found after scanning all 20 columns
+        let alternating = fastest(10, || {
+            for entity in entities.iter() {
+                std::hint::black_box(storage.get_entity_component::<Tag<19>>(entity));
+                std::hint::black_box(storage.get_entity_component::<Tag<0>>(entity));
+            }
+        });
+        assert_eq!(storage.last_accessed_column.get(), 0);
+        std::println!("same type {same_type:?}, alternating {alternating:?} for twice the lookups");
+    }
+
+    // Counts its updates like `Counter`, but declares it has none
+    #[derive(Debug, Default, PartialEq)]
+    struct DataOnly {
+        n: u32,
+    }
+
+    impl Component for DataOnly {
+        const HAS_BEHAVIOR: bool = false;
+        fn setup(&mut self, _: &ComponentStorage) {}
+        fn update(&mut self, _: &ComponentStorage) {
+            self.n += 1;
+        }
+    }
+
+    #[test]
+    fn data_only_columns_are_never_updated() {
+        let mut data_only = BenchHarness::new(1000, |_| (DataOnly::default(),));
+        let data_only_report = data_only.run::<DataOnly>(3);
+        let mut with_behavior = BenchHarness::new(1000, |_| (Counter::default(),));
+        let with_behavior_report = with_behavior.run::<Counter>(3);
+        std::println!(
+            "update over 1000 components: data only {:?}, with behavior {:?}",
+            data_only_report.update,
+            with_behavior_report.update
+        );
+        let storage = data_only.storage();
+        assert!(storage
+            .iter_component::<DataOnly>()
+            .all(|(_, component)| component.n == 0));
+        let storage = with_behavior.storage();
+        assert!(storage
+            .iter_component::<Counter>()
+            .all(|(_, counter)| counter.n == 3));
+    }
+
+    #[test]
+    fn harness_reports_nonzero_timings_for_1000_entities() {
+        let mut harness = BenchHarness::new(1000, |_| (Counter::default(),));
+        let report = harness.run::<Counter>(5);
+        assert_eq!((report.entities, report.iterations), (1000, 5));
+        assert!(report.update > Duration::ZERO);
+        assert!(report.query > Duration::ZERO);
+        assert!(report.get > Duration::ZERO);
+        let counter = harness
+            .storage()
+            .get_entity_component::<Counter>(&Entity(0));
+        assert_eq!(counter.unwrap().n, 5);
+    }
+
+    #[test]
+    fn join_and_a_get_per_entity_see_the_same_pairs() {
+        let harness = BenchHarness::new(10_000, |i| {
+            (Transform { x: i as f32 }, Velocity { dx: i as f32 * 0.5 })
+        });
+        let storage = harness.storage();
+        let entities = storage.get_entities();
+        let mut joined = vec![];
+        let join = fastest(5, || {
+            joined.clear();
+            for (entity, mut transform, velocity) in storage.join_mut::<Transform, Velocity>() {
+                transform.x += velocity.dx;
+                joined.push((entity, transform.x, velocity.dx));
+            }
+        });
+        let mut fetched = vec![];
+        let naive = fastest(5, || {
+            fetched.clear();
+            for entity in entities.iter() {
+                let velocity = storage.get_entity_component::<Velocity>(entity).unwrap();
+                let mut transform = storage
+                    .get_entity_component_mut::<Transform>(entity)
+                    .unwrap();
+                transform.x += velocity.dx;
+                fetched.push((entity.clone(), transform.x, velocity.dx));
+            }
+        });
+        std::println!("integrating 10k entities: join {join:?}, get per entity {naive:?}");
+        // Both ran five times, so they only differ by the last step
+        joined.sort_by_key(|(entity, ..)| entity.clone());
+        assert_eq!(joined.len(), fetched.len());
+        for ((a, joined_x, a_dx), (b, fetched_x, b_dx)) in joined.iter().zip(fetched.iter()) {
+            assert_eq!((a, a_dx), (b, b_dx));
+            assert_eq!(joined_x + b_dx * 5.0, *fetched_x);
+        }
+    }
+
+    #[test]
+    fn dense_column_gets_skip_the_table() {
+        let dense = BenchHarness::new(10_000, |i| (Transform { x: i as f32 },));
+        let mut sparse = BenchHarness::new(10_000, |i| (Transform { x: i as f32 },));
+        // Same layout, but a sort always takes the column off the dense path
+        sparse
+            .storage_mut()
+            .sort_component::<Transform, u32>(|transform| transform.x as u32);
+        // `Transform` is the only column of both
+        assert_eq!(dense.storage().column_dense, [true]);
+        assert_eq!(sparse.storage().column_dense, [false]);
+
+        let entities = dense.storage().get_entities();
+        let time = |storage: &ComponentStorage| {
+            fastest(5, || {
+                for entity in entities.iter() {
+                    std::hint::black_box(storage.get_entity_component::<Transform>(entity));
+                }
+            })
+        };
+        let (dense_get, sparse_get) = (time(dense.storage()), time(sparse.storage()));
+        std::println!("get over 10k entities: dense {dense_get:?}, sparse {sparse_get:?}");
+        for entity in entities.iter() {
+            let dense = dense.storage().get_entity_component::<Transform>(entity);
+            let sparse = sparse.storage().get_entity_component::<Transform>(entity);
+            assert_eq!(dense.as_deref(), sparse.as_deref());
+        }
+    }
+
+    #[test]
+    fn compact_rows_against_a_map_per_entity_over_100k_entities() {
+        use core::{any::TypeId, mem::size_of};
+        use std::collections::HashMap;
+
+        use crate::{row::ComponentRow, testing::Mesh};
+
+        let harness = BenchHarness::new(100_000, |i| {
+            (
+                Transform { x: i as f32 },
+                Mesh::default(),
+                Velocity { dx: 1.0 },
+            )
+        });
+        let rows = &harness.storage().component_table;
+        let maps: Vec<Option<HashMap<TypeId, u32>>> = rows
+            .iter()
+            .map(|row| {
+                row.as_ref()
+                    .map(|row| row.iter().map(|(&k, &v)| (k, v)).collect())
+            })
+            .collect();
+        let row_bytes: usize = rows.len() * size_of::<Option<ComponentRow>>()
+            + rows
+                .iter()
+                .flatten()
+                .map(ComponentRow::heap_size)
+                .sum::<usize>();
+        // A lower bound, it leaves out the control bytes and the unused buckets
+        let map_bytes: usize = maps.len() * size_of::<Option<HashMap<TypeId, u32>>>()
+            + maps
+                .iter()
+                .flatten()
+                .map(|map| map.capacity() * size_of::<(TypeId, u32)>())
+                .sum::<usize>();
+
+        let types = [
+            TypeId::of::<Transform>(),
+            TypeId::of::<Mesh>(),
+            TypeId::of::<Velocity>(),
+        ];
+        let row_get = fastest(5, || {
+            for row in rows.iter().flatten() {
+                for type_id in types.iter() {
+                    std::hint::black_box(row.get(type_id));
+                }
+            }
+        });
+        let map_get = fastest(5, || {
+            for map in maps.iter().flatten() {
+                for type_id in types.iter() {
+                    std::hint::black_box(map.get(type_id));
+                }
+            }
+        });
+        std::println!(
+            "tables of 100k entities with 3 components: rows {row_bytes} bytes, get {row_get:?}; \
+             maps at least {map_bytes} bytes, get {map_get:?}"
+        );
+        assert!(row_bytes < map_bytes);
+        for (row, map) in rows.iter().zip(maps.iter()) {
+            let (row, map) = (row.as_ref().unwrap(), map.as_ref().unwrap());
+            for type_id in types.iter() {
+                assert_eq!(row.get(type_id), map.get(type_id));
+            }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use alloc::{vec, vec::Vec};
+use core::{
+    any::TypeId,
+    cell::{Ref, RefMut},
+};
+
+use crate::component::{Component, ComponentStorage, Entity};
+
+pub trait Bundle {
+    // Reserves room for `additional` more bundles of this type ahead of a batch spawn
+    fn reserve(_storage: &mut ComponentStorage, _additional: usize)
+    where
+        Self: Sized,
+    {
+    }
+
+    // Pushes the `TypeId` and slot of every inserted component into `component_ids`
+    fn insert(
+        self,
+        storage: &mut ComponentStorage,
+        entity: &Entity,
+        component_ids: &mut Vec<(TypeId, u32)>,
+    );
+}
+
+macro_rules! impl_bundle {
+    ($($component:ident),+) => {
+        impl<$($component: Component + 'static),+> Bundle for ($($component,)+) {
+            fn reserve(storage: &mut ComponentStorage, additional: usize) {
+                $(storage.reserve_components::<$component>(additional);)+
+            }
+
+            #[allow(non_snake_case)]
+            fn insert(
+                self,
+                storage: &mut ComponentStorage,
+                entity: &Entity,
+                component_ids: &mut Vec<(TypeId, u32)>,
+            ) {
+                let ($($component,)+) = self;
+                $(
+                    if let Some(id) = storage.insert_component(entity, $component) {
+                        component_ids.push((TypeId::of::<$component>(), id));
+                    }
+                )+
+            }
+        }
+    };
+}
+
+impl_bundle!(A);
+impl_bundle!(A, B);
+impl_bundle!(A, B, C);
+impl_bundle!(A, B, C, D);
+impl_bundle!(A, B, C, D, E);
+impl_bundle!(A, B, C, D, E, F);
+impl_bundle!(A, B, C, D, E, F, G);
+impl_bundle!(A, B, C, D, E, F, G, H);
+
+// Resolves the spawned components through the slots captured at insertion time
+pub struct SpawnedEntity<'a> {
+    storage: &'a ComponentStorage,
+    entity: Entity,
+    component_ids: Vec<(TypeId, u32)>,
+}
+
+impl SpawnedEntity<'_> {
+    pub fn entity(&self) -> Entity {
+        self.entity.clone()
+    }
+
+    fn component_id<T: Component + 'static>(&self) -> Option<usize> {
+        let type_id = TypeId::of::<T>();
+        self.component_ids
+            .iter()
+            .find(|(id_type, _)| *id_type == type_id)
+            .map(|(_, component_id)| *component_id as usize)
+    }
+
+    pub fn get<T: Component + 'static>(&self) -> Option<Ref<'_, T>> {
+        let component_vec = self.storage.get_component_vec::<T>()?;
+        let component = component_vec.get(self.component_id::<T>()?)?;
+        Some(component.borrow())
+    }
+
+    pub fn get_mut<T: Component + 'static>(&self) -> Option<RefMut<'_, T>> {
+        let component_vec = self.storage.get_component_vec::<T>()?;
+        let component = component_vec.get(self.component_id::<T>()?)?.borrow_mut();
+        self.storage.bump_version(&self.entity, TypeId::of::<T>());
+        Some(component)
+    }
+}
+
+impl ComponentStorage {
+    pub fn spawn<B: Bundle>(&mut self, bundle: B) -> Entity {
+        let entity = self.create_entity();
+        bundle.insert(self, &entity, &mut vec![]);
+        entity
+    }
+
+    pub fn spawn_batch<B: Bundle>(
+        &mut self,
+        count: usize,
+        mut factory: impl FnMut(usize) -> B,
+    ) -> Vec<Entity> {
+        B::reserve(self, count);
+        self.reserve_entities(count);
+        (0..count).map(|index| self.spawn(factory(index))).collect()
+    }
+
+    pub fn spawn_with_handle<B: Bundle>(&mut self, bundle: B) -> SpawnedEntity<'_> {
+        let entity = self.create_entity();
+        let mut component_ids = vec![];
+        bundle.insert(self, &entity, &mut component_ids);
+        SpawnedEntity {
+            storage: self,
+            entity,
+            component_ids,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Mesh, Transform};
+
+    #[test]
+    fn spawned_handle_adjusts_two_bundle_components() {
+        let mut storage = ComponentStorage::new();
+        storage.spawn((Transform { x: 0.0 },));
+        let spawned = storage.spawn_with_handle((Transform { x: 1.0 }, Mesh { label: "a".into() }));
+        spawned.get_mut::<Transform>().unwrap().x = 3.0;
+        spawned.get_mut::<Mesh>().unwrap().label = "b".into();
+        assert_eq!(spawned.get::<Transform>().unwrap().x, 3.0);
+        let entity = spawned.entity();
+
+        assert_eq!(
+            storage
+                .get_entity_component::<Transform>(&entity)
+                .unwrap()
+                .x,
+            3.0
+        );
+        assert_eq!(
+            storage.get_entity_component::<Mesh>(&entity).unwrap().label,
+            "b"
+        );
+        assert_eq!(
+            storage
+                .get_entity_component::<Transform>(&Entity(0))
+                .unwrap()
+                .x,
+            0.0
+        );
+    }
+
+    #[test]
+    fn spawn_batch_builds_each_bundle_from_its_index() {
+        let mut storage = ComponentStorage::new();
+        let entities = storage.spawn_batch(100, |i| (Transform { x: i as f32 }, Mesh::default()));
+        assert_eq!(entities.len(), 100);
+        for i in [0, 42, 99] {
+            let transform = storage
+                .get_entity_component::<Transform>(&entities[i])
+                .unwrap();
+            assert_eq!(transform.x, i as f32);
+        }
+        assert!(storage.get_component_vec::<Transform>().unwrap().capacity() >= 100);
+        assert!(storage.get_component_vec::<Mesh>().unwrap().capacity() >= 100);
+    }
+}
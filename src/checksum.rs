@@ -0,0 +1,133 @@
+use alloc::vec::Vec;
+use core::{
+    any::TypeId,
+    hash::{Hash, Hasher},
+};
+
+use crate::component::{Component, ComponentStorage, Entity};
+
+pub(crate) type HashComponentFn = fn(&ComponentStorage, &Entity, &mut dyn Hasher);
+
+// FNV-1a, unlike the std hashers its output is fixed across builds and platforms. Integers
+// go in as little endian and lengths as `u64`, so neither byte order nor pointer width matter
+struct Fnv1a(u64);
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+}
+
+impl ComponentStorage {
+    // Only registered types go into `checksum`. `key` stands in for the type in the hash and has
+    // to be the same on every peer, `type_name` isn't guaranteed to be
+    pub fn register_hashable<T: Component + Hash + 'static>(&mut self, key: &'static str) {
+        fn hash_component<T: Component + Hash + 'static>(
+            storage: &ComponentStorage,
+            entity: &Entity,
+            mut state: &mut dyn Hasher,
+        ) {
+            if let Some(component) = storage.get_entity_component::<T>(entity) {
+                component.hash(&mut state);
+            }
+        }
+        self.hash_fns
+            .insert(TypeId::of::<T>(), (key, hash_component::<T>));
+    }
+
+    // Walks entities in id order and their registered components in key order, so two worlds
+    // holding the same data match however they were built
+    pub fn checksum(&self) -> u64 {
+        let mut hash_fns: Vec<_> = self.hash_fns.iter().collect();
+        hash_fns.sort_by_key(|(_, (key, _))| *key);
+
+        let mut state = Fnv1a(0xcbf2_9ce4_8422_2325);
+        for entity in (0..self.next_entity_id()).map(Entity) {
+            if !self.is_alive(&entity) {
+                continue;
+            }
+            entity.0.hash(&mut state);
+            for (&type_id, (key, hash_component)) in hash_fns.iter() {
+                if self.has_component_type(&entity, type_id) {
+                    key.hash(&mut state);
+                    hash_component(self, &entity, &mut state);
+                }
+            }
+        }
+        state.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Hash, PartialEq)]
+    struct Health(u32);
+
+    impl Component for Health {
+        const HAS_BEHAVIOR: bool = false;
+        fn setup(&mut self, _: &ComponentStorage) {}
+        fn update(&mut self, _: &ComponentStorage) {}
+    }
+
+    #[derive(Debug, Hash, PartialEq)]
+    struct Team(u8);
+
+    impl Component for Team {
+        const HAS_BEHAVIOR: bool = false;
+        fn setup(&mut self, _: &ComponentStorage) {}
+        fn update(&mut self, _: &ComponentStorage) {}
+    }
+
+    fn world(health: u32) -> ComponentStorage {
+        let mut storage = ComponentStorage::new();
+        storage.register_hashable::<Health>("health");
+        storage.register_hashable::<Team>("team");
+        storage.spawn((Health(10), Team(1)));
+        storage.spawn((Health(health),));
+        storage
+    }
+
+    #[test]
+    fn identical_worlds_match_and_a_changed_field_differs() {
+        let first = world(20);
+        assert_eq!(first.checksum(), world(20).checksum());
+
+        // Built in a different order, same data
+        let mut reordered = ComponentStorage::new();
+        reordered.register_hashable::<Team>("team");
+        reordered.register_hashable::<Health>("health");
+        let a = reordered.spawn((Team(1),));
+        reordered.spawn((Health(20),));
+        reordered.register_component(&a, Health(10));
+        assert_eq!(first.checksum(), reordered.checksum());
+
+        assert_ne!(first.checksum(), world(21).checksum());
+
+        // Pinned, so a change to what gets hashed or how shows up here before it splits peers
+        // running different builds
+        assert_eq!(first.checksum(), 2058536624659344021);
+    }
+}
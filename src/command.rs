@@ -0,0 +1,47 @@
+use crate::{
+    bundle::Bundle,
+    component::{ComponentStorage, Entity},
+};
+use alloc::{boxed::Box, vec::Vec};
+
+type Command = Box<dyn FnOnce(&mut ComponentStorage)>;
+
+// Structural changes queued while systems run, applied in the order they were queued
+#[derive(Default)]
+pub struct CommandBuffer {
+    commands: Vec<Command>,
+}
+
+impl CommandBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, command: impl FnOnce(&mut ComponentStorage) + 'static) {
+        self.commands.push(Box::new(command));
+    }
+
+    pub fn spawn<B: Bundle + 'static>(&mut self, bundle: B) {
+        self.push(move |storage| {
+            storage.spawn(bundle);
+        });
+    }
+
+    pub fn remove_entity(&mut self, entity: Entity) {
+        self.push(move |storage| storage.remove_entity(entity));
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    pub fn apply(&mut self, storage: &mut ComponentStorage) {
+        for command in self.commands.drain(..) {
+            command(storage);
+        }
+    }
+}
@@ -1,36 +1,243 @@
-use std::{
+#[cfg(feature = "instrumentation")]
+use alloc::string::ToString;
+use alloc::{boxed::Box, format, rc::Rc, string::String, vec, vec::Vec};
+use core::{
     any::{Any, TypeId},
-    borrow::BorrowMut,
-    cell::{Ref, RefCell, RefMut},
-    collections::HashMap,
+    cell::{Cell, Ref, RefCell, RefMut},
+    fmt, mem,
+    ops::Range,
+};
+
+use crate::{
+    checksum::HashComponentFn,
+    dyn_component::DynComponents,
+    error::ComponentError,
+    logger::{self, EcsLogger},
+    relation::RelationId,
+    row::ComponentRow,
+    transaction::{snapshot_column, Checkpoint, SnapshotColumnFn},
+    visit::ComponentVisitor,
+    Map,
 };
 pub trait Component: PartialEq {
+    // Data-only components set this to false so the setup/update passes skip their column
+    const HAS_BEHAVIOR: bool = true;
+    // Types introduced through `register_type` with this set become default components
+    const AUTO_ATTACH: bool = false;
+
     fn setup(&mut self, world: &ComponentStorage);
     fn update(&mut self, world: &ComponentStorage);
+
+    // Called by `update_components_with`, `context` is the value passed there and can be
+    // recovered with `downcast_mut`
+    fn update_with_context(&mut self, world: &ComponentStorage, context: &mut dyn Any) {
+        let _ = context;
+        self.update(world);
+    }
+
+    // Called from `remove_entity` while the rest of the world is still intact. Slots shared with
+    // other entities are left alone, their data outlives this entity
+    fn on_despawn(&mut self, entity: &Entity, world: &ComponentStorage) {
+        let _ = (entity, world);
+    }
+
+    // Change detection goes through this instead of `PartialEq`, so float-bearing components
+    // can ignore differences up to `epsilon`
+    fn changed_since(&self, other: &Self, epsilon: f32) -> bool {
+        let _ = epsilon;
+        self != other
+    }
+
+    // Components holding `Entity` references translate them here when their world is merged
+    fn remap_entities(&mut self, remap: &dyn Fn(Entity) -> Entity) {
+        let _ = remap;
+    }
 }
 
+pub type ComponentVec<T> = Vec<RefCell<T>>;
+
+// See `ComponentStorage::split_columns_mut`
+pub type SplitColumns<'a, A, B> = (
+    Option<&'a mut ComponentVec<A>>,
+    Option<&'a mut ComponentVec<B>>,
+);
+
+// Highest id `create_entity_with_id` accepts, every row up to the id is allocated
+pub const MAX_ENTITY_ID: u32 = (1 << 24) - 1;
+
+// Index of a column in `component_vectors`, also its bit in the component masks
+pub type ComponentId = usize;
+
 pub trait AsAny {
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
 }
 
 pub trait ComponentArray: AsAny {
-    fn setup_components(&self, world: &ComponentStorage);
-    fn update_components(&self, world: &ComponentStorage);
+    // Only slots whose `set_up` flag is still false get their `setup` called
+    fn setup_components(&self, world: &ComponentStorage, set_up: &mut [bool]);
+    // Slots flagged in `disabled` are skipped, missing trailing flags count as enabled
+    fn update_components(&self, world: &ComponentStorage, disabled: &[bool]);
+    fn update_components_with(
+        &self,
+        world: &ComponentStorage,
+        disabled: &[bool],
+        context: &mut dyn Any,
+    );
+    // Goes through `get_mut`, the column must not be reachable from `world` meanwhile
+    fn update_components_mut(&mut self, world: &ComponentStorage, disabled: &[bool]);
+    // Moves the cells out, leaving an empty column of the same type behind
+    fn take(&mut self) -> Box<dyn ComponentArray>;
+    fn despawn_component(&self, world: &ComponentStorage, slot: u32, entity: &Entity);
+    // `owners[slot]` are the entities holding that slot
+    fn visit_components(
+        &self,
+        world: &ComponentStorage,
+        owners: &[Vec<Entity>],
+        visitor: &mut dyn ComponentVisitor,
+    );
+    fn type_name(&self) -> &'static str;
+    fn component_type_id(&self) -> TypeId;
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    // These don't touch `component_table`, callers are responsible for fixing up slot ids
+    fn clear(&mut self);
+    fn swap_remove(&mut self, index: usize);
+    fn shrink_to_fit(&mut self);
+    // Empties the column into `target`, `owners[slot]` are the target entities holding that slot
+    fn merge_into(
+        &mut self,
+        target: &mut ComponentStorage,
+        owners: &[Vec<Entity>],
+        remap: &dyn Fn(Entity) -> Entity,
+    );
 }
 
 impl<T: Component + 'static> ComponentArray for Vec<RefCell<T>> {
-    fn setup_components(&self, world: &ComponentStorage) {
-        self.iter().for_each(|c| {
-            let mut component = c.borrow_mut();
-            component.setup(world);
-        })
+    fn setup_components(&self, world: &ComponentStorage, set_up: &mut [bool]) {
+        if !T::HAS_BEHAVIOR {
+            return;
+        }
+        for (c, set_up) in self.iter().zip(set_up.iter_mut()) {
+            if *set_up {
+                continue;
+            }
+            if let Some(mut component) = world.borrow_mut_with_policy(c) {
+                component.setup(world);
+                *set_up = true;
+            }
+        }
     }
-    fn update_components(&self, world: &ComponentStorage) {
-        self.iter().for_each(|c| {
-            let mut component = c.borrow_mut();
-            component.update(world);
-        })
+    fn update_components(&self, world: &ComponentStorage, disabled: &[bool]) {
+        if !T::HAS_BEHAVIOR {
+            return;
+        }
+        for (slot, c) in self.iter().enumerate() {
+            if disabled.get(slot).copied().unwrap_or(false) {
+                continue;
+            }
+            if let Some(mut component) = world.borrow_mut_with_policy(c) {
+                component.update(world);
+            }
+        }
+    }
+    fn update_components_with(
+        &self,
+        world: &ComponentStorage,
+        disabled: &[bool],
+        context: &mut dyn Any,
+    ) {
+        if !T::HAS_BEHAVIOR {
+            return;
+        }
+        for (slot, c) in self.iter().enumerate() {
+            if disabled.get(slot).copied().unwrap_or(false) {
+                continue;
+            }
+            if let Some(mut component) = world.borrow_mut_with_policy(c) {
+                component.update_with_context(world, context);
+            }
+        }
+    }
+    fn update_components_mut(&mut self, world: &ComponentStorage, disabled: &[bool]) {
+        if !T::HAS_BEHAVIOR {
+            return;
+        }
+        for (slot, c) in self.iter_mut().enumerate() {
+            if disabled.get(slot).copied().unwrap_or(false) {
+                continue;
+            }
+            c.get_mut().update(world);
+        }
+    }
+    fn take(&mut self) -> Box<dyn ComponentArray> {
+        Box::new(core::mem::take(self))
+    }
+    fn despawn_component(&self, world: &ComponentStorage, slot: u32, entity: &Entity) {
+        let Some(c) = self.get(slot as usize) else {
+            return;
+        };
+        if let Some(mut component) = world.borrow_mut_with_policy(c) {
+            component.on_despawn(entity, world);
+        }
+    }
+    fn visit_components(
+        &self,
+        world: &ComponentStorage,
+        owners: &[Vec<Entity>],
+        visitor: &mut dyn ComponentVisitor,
+    ) {
+        for (c, owners) in self.iter().zip(owners) {
+            if owners.is_empty() {
+                continue;
+            }
+            let Some(component) = world.borrow_with_policy(c) else {
+                continue;
+            };
+            for owner in owners {
+                visitor.visit(owner.clone(), core::any::type_name::<T>(), &*component);
+            }
+        }
+    }
+    fn type_name(&self) -> &'static str {
+        core::any::type_name::<T>()
+    }
+    fn component_type_id(&self) -> TypeId {
+        TypeId::of::<T>()
+    }
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+    fn clear(&mut self) {
+        Vec::clear(self)
+    }
+    fn swap_remove(&mut self, index: usize) {
+        Vec::swap_remove(self, index);
+    }
+    fn shrink_to_fit(&mut self) {
+        Vec::shrink_to_fit(self)
+    }
+    fn merge_into(
+        &mut self,
+        target: &mut ComponentStorage,
+        owners: &[Vec<Entity>],
+        remap: &dyn Fn(Entity) -> Entity,
+    ) {
+        for (component, owners) in self.drain(..).zip(owners) {
+            let Some((first, rest)) = owners.split_first() else {
+                continue;
+            };
+            let mut component = component.into_inner();
+            component.remap_entities(remap);
+            let Some(component_id) = target.insert_component(first, component) else {
+                continue;
+            };
+            for owner in rest {
+                target.share_slot::<T>(component_id, owner);
+            }
+        }
     }
 }
 
@@ -43,126 +250,2881 @@ impl<T: ComponentArray + 'static> AsAny for T {
     }
 }
 
+// What to do when a component is already borrowed during an update pass or query
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BorrowPolicy {
+    #[default]
+    Panic,
+    Skip,
+    Log,
+}
+
+// How a column grows once it runs out of capacity while registering components
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GrowthPolicy {
+    #[default]
+    Doubling,
+    Fixed(usize),
+}
+
+// Emitted whenever an entity gains or loses component types
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchetypeChanged {
+    pub entity: Entity,
+    pub added: Vec<TypeId>,
+    pub removed: Vec<TypeId>,
+}
+
+type InsertDefaultFn = fn(&mut ComponentStorage, &Entity);
+type CloneComponentFn = fn(&mut ComponentStorage, &Entity, &Entity);
+
 pub struct ComponentStorage {
     pub component_vectors: Vec<Box<dyn ComponentArray>>,
-    component_table: Vec<Option<HashMap<TypeId, u32>>>,
+    // Indices into `component_vectors` sorted by type name, so passes don't depend on registration order
+    pub(crate) column_order: Vec<usize>,
+    // Per column, whether each slot has had `setup` called, missing trailing entries count as false
+    pub(crate) column_setup: Vec<RefCell<Vec<bool>>>,
+    // True while every slot `i` of the column belongs to `Entity(i)`, which lets lookups skip
+    // the entity's row and go by its mask bit instead
+    pub(crate) column_dense: Vec<bool>,
+    pub(crate) component_table: Vec<Option<ComponentRow>>,
+    // One bit per column index, only the first 64 component types are representable
+    pub(crate) component_masks: Vec<u64>,
+    // Bumped whenever an entity is removed, so weak references to it stop resolving
+    pub(crate) generations: Vec<u32>,
+    // Bumped whenever an entity that keeps a component can end up at a different slot
+    pub(crate) slot_epoch: u64,
     pub entities: u32,
-    alive_entities: Vec<Entity>,
+    pub(crate) alive_entities: Vec<Entity>,
+    pub(crate) last_accessed_column: Cell<usize>,
+    // Reference counts for slots referenced by more than one entity
+    pub(crate) shared_slots: Map<(TypeId, u32), u32>,
+    // Keyed by the trait object's `TypeId`, each value is a `Vec<TraitCaster<dyn Trait>>`
+    pub(crate) trait_casters: Map<TypeId, Box<dyn Any>>,
+    borrow_policy: BorrowPolicy,
+    default_components: Vec<(TypeId, InsertDefaultFn)>,
+    growth_policies: Map<TypeId, GrowthPolicy>,
+    update_intervals: Map<TypeId, u32>,
+    initial_capacities: Map<TypeId, usize>,
+    // Number of update passes run so far
+    frame: Cell<u64>,
+    archetype_changes: Vec<ArchetypeChanged>,
+    clone_fns: Map<TypeId, CloneComponentFn>,
+    // Filled by `register_cloneable` alongside `clone_fns`
+    pub(crate) snapshot_fns: Map<TypeId, SnapshotColumnFn>,
+    // Set between `begin_transaction` and `commit` or `rollback`
+    pub(crate) transaction: Option<Box<Checkpoint>>,
+    // Slots appended by `reserve_slots` that no entity owns yet, sorted
+    pub(crate) reserved_slots: Map<TypeId, Vec<u32>>,
+    logger: Box<dyn EcsLogger>,
+    pub(crate) relations: Map<(RelationId, Entity), Vec<Entity>>,
+    pub(crate) despawn_queue: Rc<RefCell<Vec<Entity>>>,
+    // Entities with the frame they get removed at, see `despawn_after`
+    pub(crate) despawn_timers: Vec<(Entity, u64)>,
+    pub(crate) dyn_components: DynComponents,
+    pub(crate) disabled_components: Map<Entity, Vec<TypeId>>,
+    pub(crate) component_groups: Map<String, Vec<TypeId>>,
+    pub(crate) change_tick: Cell<u32>,
+    pub(crate) component_versions: RefCell<Map<(Entity, TypeId), u32>>,
+    pub(crate) hash_fns: Map<TypeId, (&'static str, HashComponentFn)>,
+    // (before, after) pairs for the setup pass
+    setup_orderings: Vec<(TypeId, TypeId)>,
+    #[cfg(feature = "instrumentation")]
+    frame_timings: RefCell<Vec<(String, std::time::Duration)>>,
+    #[cfg(feature = "instrumentation")]
+    access_counts: RefCell<Map<TypeId, (u64, u64)>>,
+}
+
+impl fmt::Debug for ComponentStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let columns: Vec<(&str, usize)> = self
+            .column_order
+            .iter()
+            .map(|&index| {
+                let component_vec = &self.component_vectors[index];
+                (component_vec.type_name(), component_vec.len())
+            })
+            .collect();
+        f.debug_struct("ComponentStorage")
+            .field("entities", &self.entities)
+            .field("alive", &self.alive_entities.len())
+            .field("columns", &columns)
+            .finish()
+    }
+}
+
+impl Default for ComponentStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Records what `last_frame_timings` and `access_stats` report for one update pass, without
+// `instrumentation` it only runs the columns
+struct PassTimer {
+    #[cfg(feature = "instrumentation")]
+    start: std::time::Instant,
+    #[cfg(feature = "instrumentation")]
+    timings: Vec<(String, std::time::Duration)>,
+}
+
+impl PassTimer {
+    fn start(storage: &ComponentStorage) -> Self {
+        let _ = storage;
+        #[cfg(feature = "instrumentation")]
+        storage.access_counts.borrow_mut().clear();
+        Self {
+            #[cfg(feature = "instrumentation")]
+            start: std::time::Instant::now(),
+            #[cfg(feature = "instrumentation")]
+            timings: vec![],
+        }
+    }
+
+    fn time_column(&mut self, type_name: &str, update: impl FnOnce()) {
+        let _ = type_name;
+        #[cfg(feature = "instrumentation")]
+        let start = std::time::Instant::now();
+        update();
+        #[cfg(feature = "instrumentation")]
+        self.timings.push((type_name.to_string(), start.elapsed()));
+    }
+
+    fn finish(self, storage: &ComponentStorage, pass: &str) {
+        let _ = (storage, pass);
+        #[cfg(feature = "instrumentation")]
+        {
+            let mut timings = self.timings;
+            timings.push((pass.to_string(), self.start.elapsed()));
+            *storage.frame_timings.borrow_mut() = timings;
+        }
+    }
 }
 
 impl ComponentStorage {
     pub fn new() -> Self {
         Self {
             component_vectors: vec![],
+            column_order: vec![],
+            column_setup: vec![],
+            column_dense: vec![],
             component_table: vec![],
+            component_masks: vec![],
+            generations: vec![],
+            slot_epoch: 0,
             entities: 0,
             alive_entities: vec![],
+            last_accessed_column: Cell::new(0),
+            shared_slots: Map::new(),
+            trait_casters: Map::new(),
+            borrow_policy: BorrowPolicy::default(),
+            default_components: vec![],
+            growth_policies: Map::new(),
+            update_intervals: Map::new(),
+            initial_capacities: Map::new(),
+            frame: Cell::new(0),
+            archetype_changes: vec![],
+            clone_fns: Map::new(),
+            snapshot_fns: Map::new(),
+            transaction: None,
+            reserved_slots: Map::new(),
+            logger: logger::default_logger(),
+            relations: Map::new(),
+            despawn_queue: Rc::new(RefCell::new(vec![])),
+            despawn_timers: vec![],
+            dyn_components: DynComponents::default(),
+            disabled_components: Map::new(),
+            component_groups: Map::new(),
+            change_tick: Cell::new(0),
+            component_versions: RefCell::new(Map::new()),
+            hash_fns: Map::new(),
+            setup_orderings: vec![],
+            #[cfg(feature = "instrumentation")]
+            frame_timings: RefCell::new(vec![]),
+            #[cfg(feature = "instrumentation")]
+            access_counts: RefCell::new(Map::new()),
+        }
+    }
+    // Makes the setup pass run every `Before` component ahead of any `After` one, so an `After`
+    // component's `setup` can read what `Before::setup` wrote on the same entity
+    pub fn order_setup<Before: Component + 'static, After: Component + 'static>(&mut self) {
+        self.setup_orderings
+            .push((TypeId::of::<Before>(), TypeId::of::<After>()));
+    }
+
+    // Columns not named in any ordering keep their type name order. Orderings on types without
+    // a column are ignored, columns caught in a cycle run last in type name order
+    fn setup_order(&self) -> Vec<usize> {
+        if self.setup_orderings.is_empty() {
+            return self.column_order.clone();
+        }
+        let column = |type_id: &TypeId| {
+            self.component_vectors
+                .iter()
+                .position(|component_vec| component_vec.component_type_id() == *type_id)
+        };
+        let mut dependencies = vec![vec![]; self.component_vectors.len()];
+        for (before, after) in self.setup_orderings.iter() {
+            let (Some(before), Some(after)) = (column(before), column(after)) else {
+                continue;
+            };
+            dependencies[after].push(before);
         }
+
+        let mut sorted = Vec::with_capacity(self.column_order.len());
+        let mut placed = vec![false; self.component_vectors.len()];
+        while sorted.len() < self.column_order.len() {
+            let next = self.column_order.iter().copied().find(|&index| {
+                !placed[index] && dependencies[index].iter().all(|&before| placed[before])
+            });
+            let Some(next) = next else {
+                self.log("setup ordering cycle, running the remaining columns by type name");
+                sorted.extend(self.column_order.iter().filter(|&&index| !placed[index]));
+                break;
+            };
+            placed[next] = true;
+            sorted.push(next);
+        }
+        sorted
     }
+
     pub fn setup_components(&self) {
-        self.component_vectors
+        for index in self.setup_order() {
+            let component_vec = &self.component_vectors[index];
+            let mut set_up = self.column_setup[index].borrow_mut();
+            set_up.resize(component_vec.len(), false);
+            component_vec.setup_components(self, &mut set_up);
+        }
+    }
+
+    // Setup runs ahead of every frame, it only reaches components that haven't been set up yet.
+    // Entities whose `DespawnGuard` was dropped or whose `despawn_after` delay ran out during a
+    // frame are removed at its end
+    pub fn run(&mut self, frames: usize, mut per_frame: impl FnMut(&mut ComponentStorage)) {
+        for _ in 0..frames {
+            self.setup_components();
+            self.update_components();
+            per_frame(self);
+            self.flush_despawns();
+        }
+    }
+
+    // Nothing in the storage points back into itself, so a whole world can be exchanged for a
+    // double buffered one. Entity ids stay valid as long as both sides spawn and remove in lockstep
+    pub fn swap(&mut self, other: &mut ComponentStorage) {
+        core::mem::swap(self, other);
+    }
+
+    // The next setup pass runs on these slots again, as it does for a freshly inserted component
+    pub(crate) fn reset_set_up<T: Component + 'static>(&mut self, slots: &[u32]) {
+        let Some(index) = self.get_component_vec_index::<T>() else {
+            return;
+        };
+        let set_up = self.column_setup[index].get_mut();
+        for &slot in slots {
+            if let Some(set_up) = set_up.get_mut(slot as usize) {
+                *set_up = false;
+            }
+        }
+    }
+
+    fn column_set_up_mut(&mut self, index: usize, len: usize) -> &mut Vec<bool> {
+        let set_up = self.column_setup[index].get_mut();
+        set_up.resize(len, false);
+        set_up
+    }
+
+    // Disabled and reserved slots, only as long as the highest of them and empty while there
+    // are none
+    fn disabled_slots(&self, index: usize) -> Vec<bool> {
+        let mut disabled = vec![];
+        let type_id = self.component_vectors[index].component_type_id();
+        let reserved = self.reserved_slots.get(&type_id).into_iter().flatten();
+        if self.disabled_components.is_empty() && reserved.clone().next().is_none() {
+            return disabled;
+        }
+        let slots = self
+            .disabled_components
             .iter()
-            .for_each(|component_vec| component_vec.setup_components(self));
+            .filter(|(_, types)| types.contains(&type_id))
+            .filter_map(|(entity, _)| {
+                self.component_table[entity.0 as usize]
+                    .as_ref()
+                    .and_then(|table| table.get(&type_id))
+            });
+        for &slot in slots.chain(reserved) {
+            if disabled.len() <= slot as usize {
+                disabled.resize(slot as usize + 1, false);
+            }
+            disabled[slot as usize] = true;
+        }
+        disabled
+    }
+
+    // Each `update_components` variant advances it by one
+    pub fn current_frame(&self) -> u64 {
+        self.frame.get()
+    }
+
+    fn is_column_due(&self, index: usize) -> bool {
+        let type_id = self.component_vectors[index].component_type_id();
+        let interval = self.update_intervals.get(&type_id).copied().unwrap_or(1);
+        self.frame.get().is_multiple_of(interval.max(1) as u64)
     }
 
     pub fn update_components(&self) {
-        for component in self.component_vectors.iter() {
-            component.update_components(self);
+        let mut timer = PassTimer::start(self);
+        for &index in self.column_order.iter() {
+            if self.is_column_due(index) {
+                let component_vec = &self.component_vectors[index];
+                timer.time_column(component_vec.type_name(), || {
+                    component_vec.update_components(self, &self.disabled_slots(index))
+                });
+            }
+        }
+        timer.finish(self, "update_components");
+        self.frame.set(self.frame.get() + 1);
+    }
+
+    // Same pass as `update_components` without any `RefCell` borrows. Each column is moved out
+    // of the storage while it updates, so a component sees no components of its own type
+    pub fn update_components_mut(&mut self) {
+        let mut timer = PassTimer::start(self);
+        for order in 0..self.column_order.len() {
+            let index = self.column_order[order];
+            if !self.is_column_due(index) {
+                continue;
+            }
+            let disabled = self.disabled_slots(index);
+            let mut column = self.component_vectors[index].take();
+            timer.time_column(column.type_name(), || {
+                column.update_components_mut(self, &disabled)
+            });
+            self.component_vectors[index] = column;
+        }
+        timer.finish(self, "update_components_mut");
+        self.frame.set(self.frame.get() + 1);
+    }
+
+    // Same pass as `update_components`, handing `context` to every component
+    pub fn update_components_with<C: 'static>(&self, context: &mut C) {
+        let mut timer = PassTimer::start(self);
+        for &index in self.column_order.iter() {
+            if self.is_column_due(index) {
+                let component_vec = &self.component_vectors[index];
+                timer.time_column(component_vec.type_name(), || {
+                    component_vec.update_components_with(self, &self.disabled_slots(index), context)
+                });
+            }
+        }
+        timer.finish(self, "update_components_with");
+        self.frame.set(self.frame.get() + 1);
+    }
+
+    // One entry per column updated in the last update pass, whichever variant ran it, followed by
+    // the whole pass under the variant's name
+    #[cfg(feature = "instrumentation")]
+    pub fn last_frame_timings(&self) -> Vec<(String, std::time::Duration)> {
+        self.frame_timings.borrow().clone()
+    }
+
+    // Reads and writes through `get_entity_component`/`get_entity_component_mut` per type,
+    // counted since the start of the last update pass
+    #[cfg(feature = "instrumentation")]
+    pub fn access_stats(&self) -> Vec<(TypeId, u64, u64)> {
+        self.access_counts
+            .borrow()
+            .iter()
+            .map(|(&type_id, &(reads, writes))| (type_id, reads, writes))
+            .collect()
+    }
+
+    #[cfg(feature = "instrumentation")]
+    fn record_access<T: 'static>(&self, write: bool) {
+        let mut access_counts = self.access_counts.borrow_mut();
+        let (reads, writes) = access_counts.entry(TypeId::of::<T>()).or_default();
+        if write {
+            *writes += 1;
+        } else {
+            *reads += 1;
+        }
+    }
+
+    #[cfg(not(feature = "instrumentation"))]
+    fn record_access<T: 'static>(&self, _write: bool) {}
+
+    pub fn set_borrow_policy(&mut self, borrow_policy: BorrowPolicy) {
+        self.borrow_policy = borrow_policy;
+    }
+
+    pub fn borrow_policy(&self) -> BorrowPolicy {
+        self.borrow_policy
+    }
+
+    pub fn set_logger(&mut self, logger: impl EcsLogger + 'static) {
+        self.logger = Box::new(logger);
+    }
+
+    pub(crate) fn log(&self, message: &str) {
+        self.logger.log(message);
+    }
+
+    pub(crate) fn borrow_with_policy<'a, T: 'static>(
+        &self,
+        component: &'a RefCell<T>,
+    ) -> Option<Ref<'a, T>> {
+        match component.try_borrow() {
+            Ok(component) => Some(component),
+            Err(err) => self.handle_borrow_failure::<T, _>(&err),
+        }
+    }
+
+    pub(crate) fn borrow_mut_with_policy<'a, T: 'static>(
+        &self,
+        component: &'a RefCell<T>,
+    ) -> Option<RefMut<'a, T>> {
+        match component.try_borrow_mut() {
+            Ok(component) => Some(component),
+            Err(err) => self.handle_borrow_failure::<T, _>(&err),
+        }
+    }
+
+    fn handle_borrow_failure<T: 'static, R>(&self, err: &dyn core::error::Error) -> Option<R> {
+        let type_name = core::any::type_name::<T>();
+        match self.borrow_policy {
+            BorrowPolicy::Panic => panic!("failed to borrow {type_name}: {err}"),
+            BorrowPolicy::Skip => None,
+            BorrowPolicy::Log => {
+                self.log(&format!("skipping {type_name}, failed to borrow: {err}"));
+                None
+            }
+        }
+    }
+
+    // Systems tend to hit the same type repeatedly, so the last matching column is checked first
+    fn get_component_vec_index<T: Component + 'static>(&self) -> Option<usize> {
+        let is_column =
+            |component_vec: &dyn ComponentArray| component_vec.as_any().is::<Vec<RefCell<T>>>();
+
+        let cached = self.last_accessed_column.get();
+        if self
+            .component_vectors
+            .get(cached)
+            .is_some_and(|c| is_column(c.as_ref()))
+        {
+            return Some(cached);
         }
+
+        let index = self
+            .component_vectors
+            .iter()
+            .position(|c| is_column(c.as_ref()))?;
+        self.last_accessed_column.set(index);
+        Some(index)
     }
 
     pub fn get_component_vec<T: Component + 'static>(&self) -> Option<&Vec<RefCell<T>>> {
-        self.component_vectors.iter().find_map(|component_vec| {
-            let component_vec_ref = component_vec.as_ref();
-            component_vec_ref.as_any().downcast_ref::<Vec<RefCell<T>>>()
-        })
+        let index = self.get_component_vec_index::<T>()?;
+        let component_vec_ref = self.component_vectors[index].as_ref();
+        component_vec_ref.as_any().downcast_ref::<Vec<RefCell<T>>>()
     }
 
     pub fn get_component_vec_mut<T: Component + 'static>(
         &mut self,
     ) -> Option<&mut Vec<RefCell<T>>> {
-        self.component_vectors.iter_mut().find_map(|component_vec| {
-            let component_vec_ref = component_vec.as_mut();
-            component_vec_ref.as_any_mut().downcast_mut::<Vec<RefCell<T>>>()
-        })
+        let index = self.get_component_vec_index::<T>()?;
+        let component_vec_ref = self.component_vectors[index].as_mut();
+        component_vec_ref
+            .as_any_mut()
+            .downcast_mut::<Vec<RefCell<T>>>()
+    }
+
+    // Errors when `A` and `B` are the same type, a missing column comes back as `None`
+    pub fn split_columns_mut<A: Component + 'static, B: Component + 'static>(
+        &mut self,
+    ) -> Result<SplitColumns<'_, A, B>, ComponentError> {
+        if TypeId::of::<A>() == TypeId::of::<B>() {
+            return Err(ComponentError::SameType(core::any::type_name::<A>()));
+        }
+        let a_index = self.get_component_vec_index::<A>();
+        let b_index = self.get_component_vec_index::<B>();
+        self.bump_column_versions::<A>();
+        self.bump_column_versions::<B>();
+
+        let (mut a_vec, mut b_vec) = (None, None);
+        for (index, component_vec) in self.component_vectors.iter_mut().enumerate() {
+            let component_vec_ref = component_vec.as_mut().as_any_mut();
+            if Some(index) == a_index {
+                a_vec = component_vec_ref.downcast_mut::<ComponentVec<A>>();
+            } else if Some(index) == b_index {
+                b_vec = component_vec_ref.downcast_mut::<ComponentVec<B>>();
+            }
+        }
+        Ok((a_vec, b_vec))
     }
 
-    // Up to user to be careful with accessing entities that are "destroyed"
+    // Splits the column into at most `n` disjoint chunks that together cover every slot
+    pub fn chunks_mut<T: Component + 'static>(&mut self, n: usize) -> Vec<&mut [RefCell<T>]> {
+        self.bump_column_versions::<T>();
+        let Some(component_vec) = self.get_component_vec_mut::<T>() else {
+            return vec![];
+        };
+
+        let chunk_size = component_vec.len().div_ceil(n.max(1)).max(1);
+        component_vec.chunks_mut(chunk_size).collect()
+    }
+
+    // Slots only this entity held are freed after their `on_despawn`, so no later pass reaches
+    // them. Slots shared with other entities stay for the rest of their sharers
     pub fn remove_entity(&mut self, entity: Entity) {
-        self.alive_entities.remove(entity.0 as usize);
+        let mut owned: Vec<(TypeId, u32)> = self
+            .component_table
+            .get(entity.0 as usize)
+            .and_then(Option::as_ref)
+            .into_iter()
+            .flatten()
+            .map(|(&type_id, &component_id)| (type_id, component_id))
+            .filter(|slot| !self.shared_slots.contains_key(slot))
+            .collect();
+        owned.sort();
+        for &(type_id, component_id) in owned.iter() {
+            let column = self
+                .component_vectors
+                .iter()
+                .find(|component_vec| component_vec.component_type_id() == type_id);
+            if let Some(component_vec) = column {
+                component_vec.despawn_component(self, component_id, &entity);
+            }
+        }
+
+        let Some(removed) = self
+            .component_table
+            .get_mut(entity.0 as usize)
+            .and_then(Option::take)
+        else {
+            return;
+        };
+        self.component_masks[entity.0 as usize] = 0;
+        self.generations[entity.0 as usize] += 1;
+        self.alive_entities.retain(|alive| *alive != entity);
+        self.remove_relations(&entity);
+        self.remove_dyn_components(&entity);
+        self.disabled_components.remove(&entity);
+
+        // Map order differs between processes, sorted the event reads the same on every client
+        let mut removed_types: Vec<TypeId> = removed.keys().copied().collect();
+        removed_types.sort();
+        for &type_id in removed_types.iter() {
+            self.forget_version(&entity, type_id);
+        }
+        for (type_id, component_id) in removed {
+            self.release_shared_slot(type_id, component_id);
+        }
+        for (type_id, component_id) in owned {
+            self.free_slot(type_id, component_id);
+        }
+        self.push_archetype_change(&entity, vec![], removed_types);
+    }
+
+    // Moves the last slot of the column into the freed one, like `remove_component` does for a
+    // known type. Freeing the last slot moves nothing, so the column stays on the dense path
+    fn free_slot(&mut self, type_id: TypeId, component_id: u32) {
+        let Some(index) = self
+            .component_vectors
+            .iter()
+            .position(|component_vec| component_vec.component_type_id() == type_id)
+        else {
+            return;
+        };
+        let len = self.component_vectors[index].len();
+        if component_id as usize >= len {
+            return;
+        }
+        self.component_vectors[index].swap_remove(component_id as usize);
+        self.column_set_up_mut(index, len)
+            .swap_remove(component_id as usize);
+        let last_id = len as u32 - 1;
+        if component_id != last_id {
+            self.remap_component_ids(type_id, |id| if id == last_id { component_id } else { id });
+        }
+    }
+
+    // Removes in ascending id order whatever order the batch is given in. Ids are never reused
+    // by `create_entity`, so this doesn't change what gets allocated next, but the despawn hooks
+    // and archetype changes come out in the same order on every client
+    pub fn despawn_batch(&mut self, entities: &[Entity]) {
+        let mut entities = entities.to_vec();
+        entities.sort();
+        entities.dedup();
+        for entity in entities {
+            self.remove_entity(entity);
+        }
     }
 
     pub fn get_entities(&self) -> Vec<Entity> {
         self.alive_entities.clone()
     }
 
+    pub fn is_alive(&self, entity: &Entity) -> bool {
+        matches!(self.component_table.get(entity.0 as usize), Some(Some(_)))
+    }
+
+    // Ids below `next_entity_id` whose row is a tombstone, left by a removal or by padding in
+    // `create_entity_with_id`. `create_entity` never hands these out again, `create_entity_with_id` can
+    pub fn free_slots(&self) -> Vec<u32> {
+        (0..self.entities)
+            .filter(|&id| self.is_slot_free(id))
+            .collect()
+    }
+
+    pub fn is_slot_free(&self, id: u32) -> bool {
+        id < self.entities && !self.is_alive(&Entity(id))
+    }
+
+    pub fn downgrade(&self, entity: &Entity) -> Option<WeakEntity> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        Some(WeakEntity {
+            entity: entity.clone(),
+            generation: self.generations[entity.0 as usize],
+        })
+    }
+
+    // `None` once the entity has been removed, even if its id was brought back since
+    pub fn resolve(&self, weak: &WeakEntity) -> Option<Entity> {
+        let generation = *self.generations.get(weak.entity.0 as usize)?;
+        (self.is_alive(&weak.entity) && generation == weak.generation).then(|| weak.entity.clone())
+    }
+
+    pub(crate) fn slot_epoch(&self) -> u64 {
+        self.slot_epoch
+    }
+
+    pub(crate) fn alive_entities(&self) -> &[Entity] {
+        &self.alive_entities
+    }
+
+    // The id the next `create_entity` call hands out, never decreases on removal
+    pub fn next_entity_id(&self) -> u32 {
+        self.entities
+    }
+
+    // Depends only on the entity id and `world_seed` (splitmix64), so the same world seed
+    // gives every entity the same value across runs and platforms
+    pub fn entity_seed(&self, entity: &Entity, world_seed: u64) -> u64 {
+        let mut z = world_seed ^ (entity.0 as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Number of entities that have not been removed
+    pub fn alive_count(&self) -> usize {
+        self.alive_entities.len()
+    }
+
     pub fn add_component_vec<T: Component + 'static>(&mut self, component_vec: Vec<RefCell<T>>) {
+        let type_name = core::any::type_name::<T>();
+        let position = self
+            .column_order
+            .partition_point(|&index| self.component_vectors[index].type_name() < type_name);
+        self.column_order
+            .insert(position, self.component_vectors.len());
         self.component_vectors.push(Box::new(component_vec));
+        self.column_setup.push(RefCell::new(vec![]));
+        self.column_dense.push(true);
+    }
+
+    // Whether `T` has a column at all, unlike `has_component` this doesn't look at any entity
+    pub fn is_registered<T: Component + 'static>(&self) -> bool {
+        self.get_component_vec_index::<T>().is_some()
+    }
+
+    // Creates an empty column for `T` unless it already has one
+    pub fn ensure_column<T: Component + 'static>(&mut self) {
+        if self.get_component_vec_index::<T>().is_none() {
+            self.add_component_vec(self.new_column::<T>(0));
+        }
+    }
+
+    pub(crate) fn column_type_names(&self) -> Vec<&'static str> {
+        self.component_vectors
+            .iter()
+            .map(|component_vec| component_vec.type_name())
+            .collect()
+    }
+
+    // `order[new_id]` is the current id of the column moved there
+    pub(crate) fn permute_columns(&mut self, order: &[ComponentId]) {
+        let mut component_vectors: Vec<_> = core::mem::take(&mut self.component_vectors)
+            .into_iter()
+            .map(Some)
+            .collect();
+        let mut column_setup: Vec<_> = core::mem::take(&mut self.column_setup)
+            .into_iter()
+            .map(Some)
+            .collect();
+        let column_dense = core::mem::take(&mut self.column_dense);
+        let mut new_ids = vec![0; order.len()];
+        for (new_id, &old_id) in order.iter().enumerate() {
+            self.component_vectors
+                .push(component_vectors[old_id].take().unwrap());
+            self.column_setup.push(column_setup[old_id].take().unwrap());
+            self.column_dense.push(column_dense[old_id]);
+            new_ids[old_id] = new_id;
+        }
+
+        for index in self.column_order.iter_mut() {
+            *index = new_ids[*index];
+        }
+        // Columns moved down from 64 or above have no bits to carry over, so the masks are
+        // rebuilt from the table rows instead of remapped
+        let columns: Map<TypeId, usize> = self
+            .component_vectors
+            .iter()
+            .enumerate()
+            .map(|(index, component_vec)| (component_vec.component_type_id(), index))
+            .collect();
+        for (mask, table) in self
+            .component_masks
+            .iter_mut()
+            .zip(self.component_table.iter())
+        {
+            *mask = table
+                .iter()
+                .flat_map(|table| table.keys())
+                .filter_map(|type_id| columns.get(type_id))
+                .fold(0, |mask, &index| {
+                    mask | 1u64.checked_shl(index as u32).unwrap_or(0)
+                });
+        }
+        self.last_accessed_column.set(0);
+    }
+
+    // Rows of removed entities are kept, so only spare capacity is released
+    pub fn shrink_to_fit(&mut self) {
+        for component_vec in self.component_vectors.iter_mut() {
+            component_vec.shrink_to_fit();
+        }
+        for set_up in self.column_setup.iter_mut() {
+            set_up.get_mut().shrink_to_fit();
+        }
+        self.component_table.shrink_to_fit();
+        self.component_masks.shrink_to_fit();
+        self.generations.shrink_to_fit();
+        self.alive_entities.shrink_to_fit();
+    }
+
+    pub(crate) fn reserve_components<T: Component + 'static>(&mut self, additional: usize) {
+        match self.get_component_vec_mut::<T>() {
+            Some(component_vec) => component_vec.reserve(additional),
+            None => self.add_component_vec(self.new_column::<T>(additional)),
+        }
+    }
+
+    fn new_column<T: Component + 'static>(&self, capacity: usize) -> Vec<RefCell<T>> {
+        let initial_capacity = self.initial_capacities.get(&TypeId::of::<T>()).copied();
+        Vec::with_capacity(capacity.max(initial_capacity.unwrap_or(0)))
+    }
+
+    pub(crate) fn reserve_entities(&mut self, additional: usize) {
+        self.component_table.reserve(additional);
+        self.component_masks.reserve(additional);
+        self.generations.reserve(additional);
+        self.alive_entities.reserve(additional);
     }
 
     fn add_component<T: Component + 'static>(&mut self, component: T) -> u32 {
+        let growth_policy = self.growth_policies.get(&TypeId::of::<T>()).copied();
         let Some(comp_vec) = self.get_component_vec_mut::<T>() else {
-            let component_vec: Vec<RefCell<T>> = vec![RefCell::new(component)];
+            let mut component_vec = match growth_policy {
+                Some(GrowthPolicy::Fixed(step)) => self.new_column::<T>(step.max(1)),
+                _ => self.new_column::<T>(0),
+            };
+            component_vec.push(RefCell::new(component));
             self.add_component_vec(component_vec);
 
             return 0;
         };
 
+        if let Some(GrowthPolicy::Fixed(step)) = growth_policy {
+            if comp_vec.len() == comp_vec.capacity() {
+                comp_vec.reserve_exact(step.max(1));
+            }
+        }
         comp_vec.push(RefCell::new(component));
-        comp_vec.len() as u32
+        comp_vec.len() as u32 - 1
+    }
+
+    // Appends `count` default slots that no entity owns yet. They can be filled in place, from
+    // several threads through `chunks_mut` on the range of the column, then handed to entities
+    // with `spawn_from_slots`. Until then the setup and update passes leave them alone
+    pub fn reserve_slots<T: Component + Default + 'static>(
+        &mut self,
+        count: usize,
+    ) -> Range<usize> {
+        self.ensure_column::<T>();
+        let (Some(index), Some(component_vec)) = (
+            self.get_component_vec_index::<T>(),
+            self.get_component_vec_mut::<T>(),
+        ) else {
+            return 0..0;
+        };
+        let start = component_vec.len();
+        component_vec.extend((0..count).map(|_| RefCell::new(T::default())));
+        let slots = start..component_vec.len();
+
+        // Flagged as set up so the setup pass skips them, `spawn_from_slots` clears the flag
+        let len = slots.end;
+        self.column_set_up_mut(index, len)[slots.clone()].fill(true);
+        self.reserved_slots
+            .entry(TypeId::of::<T>())
+            .or_default()
+            .extend(slots.clone().map(|slot| slot as u32));
+        slots
+    }
+
+    // Creates one entity per reserved slot in `slots`, each taking that slot as its `T`. Slots
+    // that weren't reserved or were already handed out are skipped
+    pub fn spawn_from_slots<T: Component + 'static>(&mut self, slots: Range<usize>) -> Vec<Entity> {
+        let type_id = TypeId::of::<T>();
+        let (Some(index), Some(reserved)) = (
+            self.get_component_vec_index::<T>(),
+            self.reserved_slots.get_mut(&type_id),
+        ) else {
+            return vec![];
+        };
+        let (taken, kept): (Vec<u32>, Vec<u32>) = reserved
+            .iter()
+            .partition(|&&slot| slots.contains(&(slot as usize)));
+        *reserved = kept;
+        if reserved.is_empty() {
+            self.reserved_slots.remove(&type_id);
+        }
+
+        let bit = self.component_mask::<T>();
+        taken
+            .into_iter()
+            .map(|slot| {
+                let slot = slot as usize;
+                if let Some(set_up) = self.column_setup[index].get_mut().get_mut(slot) {
+                    *set_up = false;
+                }
+                let entity = self.create_entity();
+                let previous = self
+                    .get_entity_component_table_mut(&entity)
+                    .and_then(|table| table.insert(type_id, slot as u32));
+                self.track_dense_slot::<T>(&entity, slot as u32);
+                self.bump_version(&entity, type_id);
+                if let Some(bit) = bit {
+                    self.component_masks[entity.0 as usize] |= bit;
+                }
+                if previous.is_none() {
+                    self.push_archetype_change(&entity, vec![type_id], vec![]);
+                }
+                entity
+            })
+            .collect()
+    }
+
+    pub fn set_growth_policy<T: Component + 'static>(&mut self, growth_policy: GrowthPolicy) {
+        self.growth_policies
+            .insert(TypeId::of::<T>(), growth_policy);
+    }
+
+    // Only applies to a column created after this call
+    pub fn set_initial_capacity<T: Component + 'static>(&mut self, capacity: usize) {
+        self.initial_capacities.insert(TypeId::of::<T>(), capacity);
+    }
+
+    // `T` is only updated on frames that are a multiple of `interval`, counting from the first
+    // update pass as frame 0
+    pub fn set_update_interval<T: Component + 'static>(&mut self, interval: u32) {
+        self.update_intervals.insert(TypeId::of::<T>(), interval);
     }
 
     pub fn create_entity(&mut self) -> Entity {
         let entity = Entity(self.entities);
-        self.component_table.push(Some(HashMap::new()));
+        self.component_table.push(Some(ComponentRow::new()));
+        self.component_masks.push(0);
+        self.generations.push(0);
         self.entities += 1;
         self.alive_entities.push(entity.clone());
+        self.attach_default_components(&entity);
         entity
     }
 
-    fn get_entity_component_table_mut(
-        &mut self,
-        entity: &Entity,
-    ) -> Option<&mut HashMap<TypeId, u32>> {
-        self.component_table.get_mut(entity.0 as usize)?.as_mut()
-    }
+    // Rows between the previous high-water mark and `id` are padded with `None`. Ids above
+    // `MAX_ENTITY_ID` are refused rather than padding the table out to them
+    pub fn create_entity_with_id(&mut self, id: u32) -> Option<Entity> {
+        if id > MAX_ENTITY_ID {
+            return None;
+        }
+        let entity = Entity(id);
+        let index = id as usize;
+        if self.component_table.len() <= index {
+            self.component_table.resize(index + 1, None);
+            self.component_masks.resize(index + 1, 0);
+            self.generations.resize(index + 1, 0);
+        }
 
-    pub fn register_component<T: Component + 'static>(&mut self, entity: &Entity, component: T) {
-        let component_id = self.add_component(component);
-        self.get_entity_component_table_mut(entity)
-            .and_then(|table| table.insert(TypeId::of::<T>(), component_id));
+        self.entities = self.entities.max(id + 1);
+        let row = &mut self.component_table[index];
+        if row.is_none() {
+            *row = Some(ComponentRow::new());
+            self.alive_entities.push(entity.clone());
+            self.attach_default_components(&entity);
+        }
+        Some(entity)
     }
 
-    fn get_entity_component_id<T: Component + 'static>(&self, entity: &Entity) -> Option<u32> {
-        let row = self.component_table.get(entity.0 as usize)?.as_ref();
-        row.and_then(|component_table| {
-            let type_id = TypeId::of::<T>();
-            let component_id = component_table.get(&type_id)?;
-            Some(*component_id)
-        })
+    // Creates the column for `T`, and with `AUTO_ATTACH` set also attaches `T::default()` to
+    // every entity created afterwards
+    pub fn register_type<T: Component + Default + 'static>(&mut self) {
+        self.ensure_column::<T>();
+        if T::AUTO_ATTACH {
+            self.register_default_component::<T>();
+        }
     }
 
-    pub fn get_entity_component<T: Component + 'static>(&self, entity: &Entity) -> Option<Ref<T>> {
-        self.get_component_vec::<T>().and_then(|component_vec| {
-            let component_id = self.get_entity_component_id::<T>(entity)?;
-            let component = component_vec.get(component_id as usize)?;
+    // Every entity created afterwards starts out with `T::default()`
+    pub fn register_default_component<T: Component + Default + 'static>(&mut self) {
+        let type_id = TypeId::of::<T>();
+        if self
+            .default_components
+            .iter()
+            .any(|(default_type, _)| *default_type == type_id)
+        {
+            return;
+        }
+        self.ensure_column::<T>();
+
+        fn insert_default<T: Component + Default + 'static>(
+            storage: &mut ComponentStorage,
+            entity: &Entity,
+        ) {
+            storage.register_component(entity, T::default());
+        }
+        self.default_components.push((type_id, insert_default::<T>));
+    }
+
+    pub fn register_cloneable<T: Component + Clone + 'static>(&mut self) {
+        fn clone_component<T: Component + Clone + 'static>(
+            storage: &mut ComponentStorage,
+            source: &Entity,
+            target: &Entity,
+        ) {
+            let Some(component) = storage
+                .get_entity_component::<T>(source)
+                .as_deref()
+                .cloned()
+            else {
+                return;
+            };
+            storage.register_component(target, component);
+        }
+        self.clone_fns
+            .insert(TypeId::of::<T>(), clone_component::<T>);
+        self.snapshot_fns
+            .insert(TypeId::of::<T>(), snapshot_column::<T>);
+    }
+
+    // Only component types registered through `register_cloneable` are copied
+    pub fn clone_entity(&mut self, source: &Entity) -> Entity {
+        let target = self.create_entity();
+        let clone_fns: Vec<CloneComponentFn> = self
+            .component_table
+            .get(source.0 as usize)
+            .and_then(Option::as_ref)
+            .into_iter()
+            .flat_map(|table| table.keys())
+            .filter_map(|type_id| self.clone_fns.get(type_id).copied())
+            .collect();
+        for clone_component in clone_fns {
+            clone_component(self, source, &target);
+        }
+        target
+    }
+
+    fn attach_default_components(&mut self, entity: &Entity) {
+        for index in 0..self.default_components.len() {
+            let (_, insert_default) = self.default_components[index];
+            insert_default(self, entity);
+        }
+    }
+
+    fn get_entity_component_table_mut(&mut self, entity: &Entity) -> Option<&mut ComponentRow> {
+        self.component_table.get_mut(entity.0 as usize)?.as_mut()
+    }
+
+    pub fn register_component<T: Component + 'static>(&mut self, entity: &Entity, component: T) {
+        self.insert_component(entity, component);
+    }
+
+    pub(crate) fn insert_component<T: Component + 'static>(
+        &mut self,
+        entity: &Entity,
+        component: T,
+    ) -> Option<u32> {
+        self.get_entity_component_table_mut(entity)?;
+
+        let type_id = TypeId::of::<T>();
+        let existing_id = self.get_entity_component_id::<T>(entity);
+        match existing_id {
+            Some(id) if !self.shared_slots.contains_key(&(type_id, id)) => {
+                if let Some(mut existing) = self.get_entity_component_mut::<T>(entity) {
+                    *existing = component;
+                }
+                let index = self.get_component_vec_index::<T>()?;
+                if let Some(set_up) = self.column_setup[index].get_mut().get_mut(id as usize) {
+                    *set_up = false;
+                }
+                return Some(id);
+            }
+            Some(id) => {
+                self.release_shared_slot(type_id, id);
+                self.slot_epoch += 1;
+            }
+            None => {}
+        }
+
+        let component_id = self.add_component(component);
+        self.get_entity_component_table_mut(entity)
+            .and_then(|table| table.insert(type_id, component_id));
+        self.track_dense_slot::<T>(entity, component_id);
+        if let Some(bit) = self.component_mask::<T>() {
+            self.component_masks[entity.0 as usize] |= bit;
+        }
+        if existing_id.is_none() {
+            self.push_archetype_change(entity, vec![type_id], vec![]);
+        }
+        self.bump_version(entity, type_id);
+        Some(component_id)
+    }
+
+    fn push_archetype_change(&mut self, entity: &Entity, added: Vec<TypeId>, removed: Vec<TypeId>) {
+        self.archetype_changes.push(ArchetypeChanged {
+            entity: entity.clone(),
+            added,
+            removed,
+        });
+    }
+
+    // Changes are buffered until drained, in the order they happened
+    pub fn drain_archetype_changes(&mut self) -> Vec<ArchetypeChanged> {
+        core::mem::take(&mut self.archetype_changes)
+    }
+
+    // Unlinks `entity` from its T slot, returning the slot only if nobody else shares it. A slot
+    // still shared with other entities stays in place
+    fn detach_component<T: Component + 'static>(&mut self, entity: &Entity) -> Option<u32> {
+        let type_id = TypeId::of::<T>();
+        let component_id = self
+            .get_entity_component_table_mut(entity)?
+            .remove(&type_id)?;
+        self.slot_epoch += 1;
+        self.forget_version(entity, type_id);
+        if let Some(bit) = self.component_mask::<T>() {
+            self.component_masks[entity.0 as usize] &= !bit;
+        }
+        self.push_archetype_change(entity, vec![], vec![type_id]);
+
+        if self.shared_slots.contains_key(&(type_id, component_id)) {
+            self.release_shared_slot(type_id, component_id);
+            return None;
+        }
+        Some(component_id)
+    }
+
+    pub fn remove_component<T: Component + 'static>(&mut self, entity: &Entity) -> Option<T> {
+        let type_id = TypeId::of::<T>();
+        let component_id = self.detach_component::<T>(entity)?;
+        let index = self.get_component_vec_index::<T>()?;
+        let component_vec = self.get_component_vec_mut::<T>()?;
+        let len = component_vec.len();
+        let component = component_vec.swap_remove(component_id as usize);
+        self.column_set_up_mut(index, len)
+            .swap_remove(component_id as usize);
+        let last_id = len as u32 - 1;
+        self.remap_component_ids(type_id, |id| if id == last_id { component_id } else { id });
+
+        Some(component.into_inner())
+    }
+
+    // Like `remove_component` but shifts the later slots down instead of moving the last one
+    // into the gap, so the column keeps its order. O(n) in the column length
+    pub fn remove_component_ordered<T: Component + 'static>(
+        &mut self,
+        entity: &Entity,
+    ) -> Option<T> {
+        let type_id = TypeId::of::<T>();
+        let component_id = self.detach_component::<T>(entity)?;
+        let index = self.get_component_vec_index::<T>()?;
+        let component_vec = self.get_component_vec_mut::<T>()?;
+        let len = component_vec.len();
+        let component = component_vec.remove(component_id as usize);
+        self.column_set_up_mut(index, len)
+            .remove(component_id as usize);
+        self.remap_component_ids(type_id, |id| if id > component_id { id - 1 } else { id });
+
+        Some(component.into_inner())
+    }
+
+    // Moves the component out so it can be worked on with non-'static borrows, see
+    // `put_component`. The slot stays in place holding `T::default()`, so its setup state and
+    // handles survive the check-out. `None` for a shared slot
+    pub fn take_component<T: Component + Default + 'static>(
+        &mut self,
+        entity: &Entity,
+    ) -> Option<T> {
+        let cell = self.get_entity_component_cell_mut::<T>(entity)?;
+        let component = mem::take(&mut *cell.borrow_mut());
+        self.bump_version(entity, TypeId::of::<T>());
+        Some(component)
+    }
+
+    // Writes back into the slot `take_component` left behind, an entity without one gets the
+    // component registered instead
+    pub fn put_component<T: Component + 'static>(&mut self, entity: &Entity, component: T) {
+        let Some(cell) = self.get_entity_component_cell_mut::<T>(entity) else {
+            self.register_component(entity, component);
+            return;
+        };
+        *cell.borrow_mut() = component;
+        self.bump_version(entity, TypeId::of::<T>());
+    }
+
+    pub fn sort_component<T: Component + 'static, K: Ord>(&mut self, key_fn: impl Fn(&T) -> K) {
+        let Some(index) = self.get_component_vec_index::<T>() else {
+            return;
+        };
+        let Some(component_vec) = self.get_component_vec_mut::<T>() else {
+            return;
+        };
+
+        let mut order: Vec<usize> = (0..component_vec.len()).collect();
+        order.sort_by_key(|&index| key_fn(&component_vec[index].borrow()));
+
+        let mut previous: Vec<Option<RefCell<T>>> = component_vec.drain(..).map(Some).collect();
+        let mut new_ids = vec![0; order.len()];
+        for (new_id, &old_id) in order.iter().enumerate() {
+            component_vec.extend(previous[old_id].take());
+            new_ids[old_id] = new_id as u32;
+        }
+
+        let set_up = self.column_set_up_mut(index, order.len());
+        let previous_set_up = core::mem::take(set_up);
+        set_up.extend(order.iter().map(|&old_id| previous_set_up[old_id]));
+
+        self.remap_component_ids(TypeId::of::<T>(), |old_id| new_ids[old_id as usize]);
+    }
+
+    // Exchanges the values, each entity keeps its slot so anything sharing
+    // either slot sees the swap too
+    pub fn swap_components<T: Component + 'static>(&mut self, a: &Entity, b: &Entity) {
+        let (Some(a_id), Some(b_id)) = (
+            self.get_entity_component_id::<T>(a),
+            self.get_entity_component_id::<T>(b),
+        ) else {
+            return;
+        };
+        let (Some(index), Some(component_vec)) = (
+            self.get_component_vec_index::<T>(),
+            self.get_component_vec_mut::<T>(),
+        ) else {
+            return;
+        };
+        component_vec.swap(a_id as usize, b_id as usize);
+
+        let len = component_vec.len();
+        self.column_set_up_mut(index, len)
+            .swap(a_id as usize, b_id as usize);
+        self.bump_version(a, TypeId::of::<T>());
+        self.bump_version(b, TypeId::of::<T>());
+    }
+
+    // Rewrites every reference to a slot of `type_id` after its column has been reordered
+    fn remap_component_ids(&mut self, type_id: TypeId, remap: impl Fn(u32) -> u32) {
+        self.slot_epoch += 1;
+        let column = self
+            .component_vectors
+            .iter()
+            .position(|component_vec| component_vec.component_type_id() == type_id);
+        if let Some(index) = column {
+            self.column_dense[index] = false;
+        }
+        for table in self.component_table.iter_mut().flatten() {
+            if let Some(component_id) = table.get_mut(&type_id) {
+                *component_id = remap(*component_id);
+            }
+        }
+
+        self.shared_slots = core::mem::take(&mut self.shared_slots)
+            .into_iter()
+            .map(|((slot_type, component_id), references)| {
+                if slot_type == type_id {
+                    ((slot_type, remap(component_id)), references)
+                } else {
+                    ((slot_type, component_id), references)
+                }
+            })
+            .collect();
+        if let Some(reserved) = self.reserved_slots.get_mut(&type_id) {
+            for slot in reserved.iter_mut() {
+                *slot = remap(*slot);
+            }
+            reserved.sort();
+        }
+    }
+
+    pub fn component_mask<T: Component + 'static>(&self) -> Option<u64> {
+        let index = self.get_component_vec_index::<T>()?;
+        1u64.checked_shl(index as u32)
+    }
+
+    pub fn matches(&self, entity: &Entity, required_mask: u64, forbidden_mask: u64) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+
+        let mask = self.component_masks[entity.0 as usize];
+        mask & required_mask == required_mask && mask & forbidden_mask == 0
+    }
+
+    // `to` reads the same slot as `from` until it is detached by `get_entity_component_mut_cow`.
+    // The `&self` mutable accessors can't detach, so they refuse shared slots instead
+    pub fn share_component<T: Component + Clone + 'static>(&mut self, from: &Entity, to: &Entity) {
+        if from == to {
+            return;
+        }
+        let Some(component_id) = self.get_entity_component_id::<T>(from) else {
+            return;
+        };
+        self.share_slot::<T>(component_id, to);
+    }
+
+    fn share_slot<T: Component + 'static>(&mut self, component_id: u32, to: &Entity) {
+        let type_id = TypeId::of::<T>();
+        let Some(table) = self.get_entity_component_table_mut(to) else {
+            return;
+        };
+        match table.insert(type_id, component_id) {
+            Some(previous) => {
+                self.release_shared_slot(type_id, previous);
+                self.slot_epoch += 1;
+            }
+            None => self.push_archetype_change(to, vec![type_id], vec![]),
+        }
+        self.track_dense_slot::<T>(to, component_id);
+        self.bump_version(to, type_id);
+        if let Some(bit) = self.component_mask::<T>() {
+            self.component_masks[to.0 as usize] |= bit;
+        }
+
+        *self
+            .shared_slots
+            .entry((type_id, component_id))
+            .or_insert(1) += 1;
+    }
+
+    pub fn get_entity_component_mut_cow<T: Component + Clone + 'static>(
+        &mut self,
+        entity: &Entity,
+    ) -> Option<RefMut<'_, T>> {
+        let component_id = self.get_entity_component_id::<T>(entity)?;
+        let type_id = TypeId::of::<T>();
+        if self.shared_slots.contains_key(&(type_id, component_id)) {
+            let copy = self.get_entity_component::<T>(entity)?.clone();
+            let copy_id = self.add_component(copy);
+            let index = self.get_component_vec_index::<T>()?;
+            let set_up = self.column_set_up_mut(index, copy_id as usize + 1);
+            set_up[copy_id as usize] = set_up[component_id as usize];
+            self.get_entity_component_table_mut(entity)?
+                .insert(type_id, copy_id);
+            self.track_dense_slot::<T>(entity, copy_id);
+            self.release_shared_slot(type_id, component_id);
+            self.slot_epoch += 1;
+        }
 
-            Some(component.borrow())
+        self.get_entity_component_mut(entity)
+    }
+
+    fn release_shared_slot(&mut self, type_id: TypeId, component_id: u32) {
+        let key = (type_id, component_id);
+        let Some(references) = self.shared_slots.get_mut(&key) else {
+            return;
+        };
+        *references -= 1;
+        if *references <= 1 {
+            self.shared_slots.remove(&key);
+        }
+    }
+
+    pub fn has_component<T: Component + 'static>(&self, entity: &Entity) -> bool {
+        self.has_component_type(entity, TypeId::of::<T>())
+    }
+
+    // For mutually exclusive component types, false for dead entities
+    pub fn exactly_one_of(&self, entity: &Entity, types: &[TypeId]) -> bool {
+        types
+            .iter()
+            .filter(|&&type_id| self.has_component_type(entity, type_id))
+            .count()
+            == 1
+    }
+
+    pub(crate) fn has_component_type(&self, entity: &Entity, type_id: TypeId) -> bool {
+        self.component_table
+            .get(entity.0 as usize)
+            .and_then(Option::as_ref)
+            .is_some_and(|table| table.contains_key(&type_id))
+    }
+
+    pub(crate) fn get_entity_component_id<T: Component + 'static>(
+        &self,
+        entity: &Entity,
+    ) -> Option<u32> {
+        let row = self.component_table.get(entity.0 as usize)?.as_ref();
+        let index = self.get_component_vec_index::<T>()?;
+        if self.column_dense[index] && index < 64 {
+            let mask = self.component_masks[entity.0 as usize];
+            return (row.is_some() && mask & (1 << index) != 0).then_some(entity.0);
+        }
+        row.and_then(|component_table| {
+            let type_id = TypeId::of::<T>();
+            let component_id = component_table.get(&type_id)?;
+            Some(*component_id)
         })
     }
 
-    pub fn get_entity_component_mut<T: Component + 'static>(
+    // Entities holding a slot of `T`, in slot order. On the dense path slot `i` can only belong
+    // to entity `i`, otherwise every row is looked at once
+    pub(crate) fn column_owners<T: Component + 'static>(&self) -> Vec<Entity> {
+        let Some(index) = self.get_component_vec_index::<T>() else {
+            return vec![];
+        };
+        let len = self.component_vectors[index].len();
+        if self.column_dense[index] && index < 64 {
+            return (0..len as u32)
+                .map(Entity)
+                .filter(|entity| self.get_entity_component_id::<T>(entity).is_some())
+                .collect();
+        }
+        let type_id = TypeId::of::<T>();
+        let mut owners = vec![vec![]; len];
+        for (id, table) in self.component_table.iter().enumerate() {
+            let slot = table.as_ref().and_then(|table| table.get(&type_id));
+            if let Some(owners) = slot.and_then(|&slot| owners.get_mut(slot as usize)) {
+                owners.push(Entity(id as u32));
+            }
+        }
+        owners.into_iter().flatten().collect()
+    }
+
+    // Called whenever an entity's row is pointed at a slot, drops the column off the dense path
+    // once a slot doesn't match its entity's id
+    fn track_dense_slot<T: Component + 'static>(&mut self, entity: &Entity, component_id: u32) {
+        if component_id == entity.0 {
+            return;
+        }
+        if let Some(index) = self.get_component_vec_index::<T>() {
+            self.column_dense[index] = false;
+        }
+    }
+
+    pub(crate) fn get_entity_component_cell<T: Component + 'static>(
+        &self,
+        entity: &Entity,
+    ) -> Option<&RefCell<T>> {
+        let component_vec = self.get_component_vec::<T>()?;
+        let component_id = self.get_entity_component_id::<T>(entity)?;
+        component_vec.get(component_id as usize)
+    }
+
+    // `None` for a slot shared with other entities, writing to it would change all of them
+    pub(crate) fn get_entity_component_cell_mut<T: Component + 'static>(
+        &self,
+        entity: &Entity,
+    ) -> Option<&RefCell<T>> {
+        let component_id = self.get_entity_component_id::<T>(entity)?;
+        if self.is_shared_slot::<T>(component_id) {
+            return None;
+        }
+        self.get_component_vec::<T>()?.get(component_id as usize)
+    }
+
+    pub(crate) fn is_shared_slot<T: Component + 'static>(&self, component_id: u32) -> bool {
+        self.shared_slots
+            .contains_key(&(TypeId::of::<T>(), component_id))
+    }
+
+    pub fn get_entity_component<T: Component + 'static>(
         &self,
         entity: &Entity,
-    ) -> Option<RefMut<T>> {
-        self.get_component_vec::<T>().and_then(|component_vec| {
-            let component_id = self.get_entity_component_id::<T>(entity)?;
-            let component = component_vec.get(component_id as usize)?;
+    ) -> Option<Ref<'_, T>> {
+        let component = self.get_entity_component_cell::<T>(entity)?;
+        self.record_access::<T>(false);
+        Some(component.borrow())
+    }
+
+    // For FFI, the pointer bypasses the `RefCell` borrow flag and is only valid until the next
+    // structural change to the storage. Dereferencing it while a `RefMut` is live is undefined
+    pub fn raw_component_ptr<T: Component + 'static>(&self, entity: &Entity) -> Option<*const T> {
+        self.get_entity_component_cell::<T>(entity)
+            .map(|component| component.as_ptr() as *const T)
+    }
 
-            Some(component.borrow_mut())
+    // Writes through it also bypass sharing, every entity sharing the slot sees them. Handing
+    // it out counts as the write for `component_version`
+    pub fn raw_component_ptr_mut<T: Component + 'static>(&self, entity: &Entity) -> Option<*mut T> {
+        let component = self.get_entity_component_cell::<T>(entity)?.as_ptr();
+        self.bump_version(entity, TypeId::of::<T>());
+        Some(component)
+    }
+
+    pub fn get_with_slot<T: Component + 'static>(
+        &self,
+        entity: &Entity,
+    ) -> Option<(u32, Ref<'_, T>)> {
+        let component_id = self.get_entity_component_id::<T>(entity)?;
+        let component = self.get_component_vec::<T>()?.get(component_id as usize)?;
+        Some((component_id, component.borrow()))
+    }
+
+    // Falls back to `T::default()` when the entity is dead or lacks the component
+    pub fn get_or_default<T: Component + Default + Clone + 'static>(&self, entity: &Entity) -> T {
+        if !self.is_alive(entity) {
+            return T::default();
+        }
+        self.get_entity_component::<T>(entity)
+            .map(|component| component.clone())
+            .unwrap_or_default()
+    }
+
+    // Duplicate entities just share a borrow, but holding these blocks any mutable access to them
+    pub fn get_many<T: Component + 'static>(&self, entities: &[Entity]) -> Vec<Option<Ref<'_, T>>> {
+        entities
+            .iter()
+            .map(|entity| self.get_entity_component::<T>(entity))
+            .collect()
+    }
+
+    pub fn try_get_entity_component<T: Component + 'static>(
+        &self,
+        entity: &Entity,
+    ) -> Result<Ref<'_, T>, ComponentError> {
+        self.get_entity_component_cell_checked::<T>(entity)?
+            .try_borrow()
+            .map_err(|_| ComponentError::AlreadyBorrowed {
+                entity: entity.clone(),
+                type_name: core::any::type_name::<T>(),
+            })
+    }
+
+    pub fn try_get_entity_component_mut<T: Component + 'static>(
+        &self,
+        entity: &Entity,
+    ) -> Result<RefMut<'_, T>, ComponentError> {
+        let component = self.get_entity_component_cell_checked::<T>(entity)?;
+        if self
+            .get_entity_component_id::<T>(entity)
+            .is_some_and(|component_id| self.is_shared_slot::<T>(component_id))
+        {
+            return Err(ComponentError::SharedSlot {
+                entity: entity.clone(),
+                type_name: core::any::type_name::<T>(),
+            });
+        }
+        let component =
+            component
+                .try_borrow_mut()
+                .map_err(|_| ComponentError::AlreadyBorrowed {
+                    entity: entity.clone(),
+                    type_name: core::any::type_name::<T>(),
+                })?;
+        self.bump_version(entity, TypeId::of::<T>());
+        Ok(component)
+    }
+
+    fn get_entity_component_cell_checked<T: Component + 'static>(
+        &self,
+        entity: &Entity,
+    ) -> Result<&RefCell<T>, ComponentError> {
+        if !self.is_alive(entity) {
+            return Err(ComponentError::DeadEntity(entity.clone()));
+        }
+        self.get_entity_component_cell::<T>(entity).ok_or_else(|| {
+            ComponentError::MissingComponent {
+                entity: entity.clone(),
+                type_name: core::any::type_name::<T>(),
+            }
         })
     }
+
+    // `None` for a shared slot, see `share_component`
+    pub fn get_entity_component_mut<T: Component + 'static>(
+        &self,
+        entity: &Entity,
+    ) -> Option<RefMut<'_, T>> {
+        let component = self.get_entity_component_cell_mut::<T>(entity)?;
+        self.record_access::<T>(true);
+        let component = component.borrow_mut();
+        self.bump_version(entity, TypeId::of::<T>());
+        Some(component)
+    }
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Entity(pub u32);
+
+impl Entity {
+    pub fn index(&self) -> u32 {
+        self.0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct WeakEntity {
+    entity: Entity,
+    generation: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::testing::{Counter, Mesh, Rng, SetupCount, Tag, Transform};
+
+    #[test]
+    fn random_ids_keep_reads_and_removals_consistent() {
+        let mut storage = ComponentStorage::new();
+        let mut expected: Map<u32, f32> = Map::new();
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for step in 0..2000 {
+            // Mostly ids near the high-water mark, sometimes far above it or past the cap
+            let id = match rng.below(10) {
+                0 => rng.below(u32::MAX),
+                1 => storage.entities + rng.below(50_000),
+                _ => rng.below(storage.entities + 16),
+            };
+            match rng.below(4) {
+                0 => {
+                    let created = storage.create_entity_with_id(id);
+                    assert_eq!(created.is_some(), id <= MAX_ENTITY_ID);
+                    if created.is_some() {
+                        expected.entry(id).or_insert(f32::NAN);
+                    }
+                }
+                1 => {
+                    let value = step as f32;
+                    storage.register_component(&Entity(id), Transform { x: value });
+                    if let Some(x) = expected.get_mut(&id) {
+                        *x = value;
+                    }
+                }
+                2 => {
+                    storage.remove_entity(Entity(id));
+                    expected.remove(&id);
+                }
+                _ => {}
+            }
+
+            let read = storage
+                .get_entity_component::<Transform>(&Entity(id))
+                .map(|transform| transform.x);
+            match expected.get(&id) {
+                Some(x) if !x.is_nan() => assert_eq!(read, Some(*x)),
+                _ => assert_eq!(read, None),
+            }
+            assert_eq!(storage.is_alive(&Entity(id)), expected.contains_key(&id));
+            assert!(storage.entities <= MAX_ENTITY_ID + 1);
+        }
+        assert_eq!(storage.alive_count(), expected.len());
+        assert_eq!(storage.validate_integrity(), Ok(()));
+    }
+
+    #[test]
+    fn create_entity_with_id_refuses_ids_above_the_cap() {
+        let mut storage = ComponentStorage::new();
+        assert!(storage.create_entity_with_id(u32::MAX).is_none());
+        assert!(storage.create_entity_with_id(MAX_ENTITY_ID + 1).is_none());
+        assert_eq!(storage.entities, 0);
+        assert!(storage.component_table.is_empty());
+
+        let far = storage.create_entity_with_id(1000).unwrap();
+        storage.register_component(&far, Transform { x: 3.0 });
+        assert_eq!(
+            storage.get_entity_component::<Transform>(&far).unwrap().x,
+            3.0
+        );
+        assert!(storage
+            .get_entity_component::<Transform>(&Entity(500))
+            .is_none());
+        storage.register_component(&Entity(500), Transform { x: 9.0 });
+        assert!(storage
+            .get_entity_component::<Transform>(&Entity(500))
+            .is_none());
+        storage.remove_entity(Entity(500));
+        storage.remove_entity(Entity(99_999));
+        assert_eq!(storage.create_entity().0, 1001);
+    }
+
+    #[test]
+    fn masks_include_and_exclude_entities() {
+        let mut storage = ComponentStorage::new();
+        let a = storage.create_entity();
+        let b = storage.create_entity();
+        storage.register_component(&a, Transform { x: 1.0 });
+        storage.register_component(&b, Transform { x: 1.0 });
+        storage.register_component(&b, Mesh::default());
+        let transform = storage.component_mask::<Transform>().unwrap();
+        let mesh = storage.component_mask::<Mesh>().unwrap();
+
+        assert!(storage.matches(&a, transform, mesh));
+        assert!(!storage.matches(&b, transform, mesh));
+        assert!(storage.matches(&b, transform | mesh, 0));
+        assert!(!storage.matches(&a, transform | mesh, 0));
+
+        storage.remove_component::<Mesh>(&b);
+        assert!(storage.matches(&b, transform, mesh));
+        storage.remove_entity(a.clone());
+        assert!(!storage.matches(&a, 0, 0));
+    }
+
+    #[test]
+    fn mutating_a_shared_mesh_leaves_the_other_entity_unaffected() {
+        let mut storage = ComponentStorage::new();
+        let a = storage.spawn((Mesh {
+            label: "cube".into(),
+        },));
+        let b = storage.create_entity();
+        let c = storage.create_entity();
+        storage.share_component::<Mesh>(&a, &b);
+        storage.share_component::<Mesh>(&a, &c);
+        assert_eq!(
+            storage.get_entity_component::<Mesh>(&b).unwrap().label,
+            "cube"
+        );
+        assert_eq!(storage.get_component_vec::<Mesh>().unwrap().len(), 1);
+
+        // The `&self` paths can't detach the slot, so they refuse to write to it
+        assert!(storage.get_entity_component_mut::<Mesh>(&b).is_none());
+        assert!(matches!(
+            storage.try_get_entity_component_mut::<Mesh>(&b),
+            Err(ComponentError::SharedSlot { .. })
+        ));
+        assert!(storage.sync_get_mut::<Mesh>(&b).is_none());
+        let mut visited = 0;
+        storage.for_each_present::<Mesh>(&[a.clone(), b.clone()], |_, _| visited += 1);
+        assert_eq!(visited, 0);
+
+        storage
+            .get_entity_component_mut_cow::<Mesh>(&b)
+            .unwrap()
+            .label = "sphere".into();
+        assert_eq!(
+            storage.get_entity_component::<Mesh>(&a).unwrap().label,
+            "cube"
+        );
+        assert_eq!(
+            storage.get_entity_component::<Mesh>(&c).unwrap().label,
+            "cube"
+        );
+        assert_eq!(
+            storage.get_entity_component::<Mesh>(&b).unwrap().label,
+            "sphere"
+        );
+        storage.get_entity_component_mut::<Mesh>(&b).unwrap().label = "torus".into();
+        assert_eq!(
+            storage.get_entity_component::<Mesh>(&a).unwrap().label,
+            "cube"
+        );
+
+        // Once `c` is gone `a` is the slot's only owner and gets written in place
+        storage.remove_entity(c);
+        storage.get_entity_component_mut::<Mesh>(&a).unwrap().label = "a".into();
+        assert_eq!(storage.get_component_vec::<Mesh>().unwrap().len(), 2);
+        assert_eq!(storage.validate_integrity(), Ok(()));
+    }
+
+    #[test]
+    fn sorted_column_iterates_back_to_front() {
+        let mut storage = ComponentStorage::new();
+        let entities: Vec<Entity> = [3.0, 1.0, 2.0]
+            .into_iter()
+            .map(|x| storage.spawn((Transform { x },)))
+            .collect();
+        storage.sort_component::<Transform, i32>(|transform| -(transform.x as i32));
+
+        let order: Vec<f32> = storage
+            .get_component_vec::<Transform>()
+            .unwrap()
+            .iter()
+            .map(|component| component.borrow().x)
+            .collect();
+        assert_eq!(order, vec![3.0, 2.0, 1.0]);
+        for (entity, x) in entities.iter().zip([3.0, 1.0, 2.0]) {
+            assert_eq!(
+                storage.get_entity_component::<Transform>(entity).unwrap().x,
+                x
+            );
+        }
+        assert_eq!(storage.validate_integrity(), Ok(()));
+    }
+
+    #[test]
+    fn next_entity_id_and_alive_count_diverge_after_a_despawn() {
+        let mut storage = ComponentStorage::new();
+        let entities: Vec<Entity> = (0..3).map(|_| storage.create_entity()).collect();
+        storage.remove_entity(entities[1].clone());
+        assert_eq!(storage.next_entity_id(), 3);
+        assert_eq!(storage.alive_count(), 2);
+    }
+
+    #[test]
+    fn entities_work_as_hash_and_btree_keys() {
+        let mut storage = ComponentStorage::new();
+        let a = storage.create_entity();
+        let b = storage.create_entity();
+
+        let set: std::collections::HashSet<Entity> = [a.clone(), b.clone(), a.clone()].into();
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&b));
+
+        let mut names = alloc::collections::BTreeMap::new();
+        names.insert(b.clone(), "b");
+        names.insert(a.clone(), "a");
+        assert_eq!(names.get(&a), Some(&"a"));
+        assert_eq!(names.keys().next(), Some(&a));
+    }
+
+    #[test]
+    fn removed_mesh_is_handed_back() {
+        let mut storage = ComponentStorage::new();
+        let a = storage.spawn((Mesh { label: "a".into() },));
+        let b = storage.spawn((Mesh { label: "b".into() },));
+        let c = storage.spawn((Mesh { label: "c".into() },));
+
+        assert_eq!(storage.remove_component::<Mesh>(&a).unwrap().label, "a");
+        assert!(storage.get_entity_component::<Mesh>(&a).is_none());
+        assert_eq!(storage.get_entity_component::<Mesh>(&b).unwrap().label, "b");
+        assert_eq!(storage.get_entity_component::<Mesh>(&c).unwrap().label, "c");
+        assert!(!storage.matches(&a, storage.component_mask::<Mesh>().unwrap(), 0));
+        assert_eq!(storage.remove_component::<Mesh>(&c).unwrap().label, "c");
+        assert!(storage.remove_component::<Mesh>(&c).is_none());
+        assert_eq!(storage.validate_integrity(), Ok(()));
+    }
+
+    #[test]
+    fn skip_policy_skips_an_update_whose_component_is_borrowed() {
+        let mut storage = ComponentStorage::new();
+        let a = storage.spawn((Counter::default(),));
+        let b = storage.spawn((Counter::default(),));
+        storage.set_borrow_policy(BorrowPolicy::Skip);
+        {
+            let _held = storage.get_entity_component::<Counter>(&a).unwrap();
+            storage.update_components();
+        }
+        assert_eq!(storage.get_entity_component::<Counter>(&a).unwrap().n, 0);
+        assert_eq!(storage.get_entity_component::<Counter>(&b).unwrap().n, 1);
+    }
+
+    #[test]
+    fn columns_are_managed_through_dyn_component_array() {
+        let mut storage = ComponentStorage::new();
+        storage.spawn_batch(3, |i| (Transform { x: i as f32 },));
+        let column: &mut dyn ComponentArray = storage.component_vectors[0].as_mut();
+        assert_eq!(column.len(), 3);
+        column.swap_remove(0);
+        assert_eq!(column.len(), 2);
+        let cells = column
+            .as_any()
+            .downcast_ref::<ComponentVec<Transform>>()
+            .unwrap();
+        assert_eq!(cells[0].borrow().x, 2.0);
+        column.clear();
+        assert!(column.is_empty());
+        assert!(column.type_name().ends_with("Transform"));
+    }
+
+    std::thread_local! {
+        static UPDATES: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+    }
+
+    #[derive(PartialEq)]
+    struct Alpha;
+
+    impl Component for Alpha {
+        fn setup(&mut self, _: &ComponentStorage) {}
+        fn update(&mut self, _: &ComponentStorage) {
+            UPDATES.with(|updates| updates.borrow_mut().push("alpha"));
+        }
+    }
+
+    #[derive(PartialEq)]
+    struct Beta;
+
+    impl Component for Beta {
+        fn setup(&mut self, _: &ComponentStorage) {}
+        fn update(&mut self, _: &ComponentStorage) {
+            UPDATES.with(|updates| updates.borrow_mut().push("beta"));
+        }
+    }
+
+    #[test]
+    fn registration_order_does_not_change_update_order() {
+        let updates = |storage: &ComponentStorage| {
+            UPDATES.with(|updates| updates.borrow_mut().clear());
+            storage.update_components();
+            UPDATES.with(|updates| updates.borrow().clone())
+        };
+        let mut first = ComponentStorage::new();
+        first.spawn((Beta, Alpha));
+        first.spawn((Alpha,));
+        let mut second = ComponentStorage::new();
+        second.spawn((Alpha, Beta));
+        second.spawn((Alpha,));
+
+        assert_eq!(updates(&first), vec!["alpha", "alpha", "beta"]);
+        assert_eq!(updates(&first), updates(&second));
+    }
+
+    #[test]
+    fn split_columns_mutate_both_in_one_scope() {
+        let mut storage = ComponentStorage::new();
+        let entity = storage.spawn((Transform { x: 1.0 }, Mesh::default()));
+        let (transforms, meshes) = storage.split_columns_mut::<Transform, Mesh>().unwrap();
+        let (transforms, meshes) = (transforms.unwrap(), meshes.unwrap());
+        transforms[0].get_mut().x = 5.0;
+        meshes[0].get_mut().label = "z".into();
+        assert_eq!(
+            storage
+                .get_entity_component::<Transform>(&entity)
+                .unwrap()
+                .x,
+            5.0
+        );
+        assert_eq!(
+            storage.get_entity_component::<Mesh>(&entity).unwrap().label,
+            "z"
+        );
+
+        let (transforms, counters) = storage.split_columns_mut::<Transform, Counter>().unwrap();
+        assert!(transforms.is_some() && counters.is_none());
+        assert!(matches!(
+            storage.split_columns_mut::<Mesh, Mesh>(),
+            Err(ComponentError::SameType(_))
+        ));
+    }
+
+    #[test]
+    fn default_components_are_attached_on_creation() {
+        let mut storage = ComponentStorage::new();
+        storage.register_default_component::<Transform>();
+        storage.register_default_component::<Transform>();
+        let created = storage.create_entity();
+        assert_eq!(
+            storage
+                .get_entity_component::<Transform>(&created)
+                .unwrap()
+                .x,
+            0.0
+        );
+        let spawned = storage.spawn((Transform { x: 3.0 },));
+        assert_eq!(
+            storage
+                .get_entity_component::<Transform>(&spawned)
+                .unwrap()
+                .x,
+            3.0
+        );
+        assert_eq!(storage.get_component_vec::<Transform>().unwrap().len(), 2);
+    }
+
+    #[cfg(feature = "instrumentation")]
+    #[derive(PartialEq)]
+    struct Slow;
+
+    #[cfg(feature = "instrumentation")]
+    impl Component for Slow {
+        fn setup(&mut self, _: &ComponentStorage) {}
+        fn update(&mut self, _: &ComponentStorage) {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "instrumentation")]
+    fn timings_are_recorded_for_a_slow_column_in_every_pass() {
+        let mut storage = ComponentStorage::new();
+        storage.spawn((Slow, Counter::default()));
+        let check = |storage: &ComponentStorage, pass: &str| {
+            let timings = storage.last_frame_timings();
+            assert_eq!(timings.len(), 3);
+            let column = |suffix| {
+                let (_, duration) = timings
+                    .iter()
+                    .find(|(name, _)| name.ends_with(suffix))
+                    .unwrap();
+                *duration
+            };
+            assert!(column("Slow") >= std::time::Duration::from_millis(5));
+            assert!(column("Counter") < column("Slow"));
+            assert_eq!(timings[2].0, pass);
+            assert!(timings[2].1 >= column("Slow"));
+        };
+        storage.update_components();
+        check(&storage, "update_components");
+        storage.update_components_mut();
+        check(&storage, "update_components_mut");
+        storage.update_components_with(&mut ());
+        check(&storage, "update_components_with");
+    }
+
+    #[test]
+    fn get_many_fetches_three_entities_at_once() {
+        let mut storage = ComponentStorage::new();
+        let entities = storage.spawn_batch(3, |i| (Transform { x: i as f32 },));
+        let fetched = storage.get_many::<Transform>(&[
+            entities[2].clone(),
+            Entity(77),
+            entities[0].clone(),
+            entities[2].clone(),
+        ]);
+        let xs: Vec<Option<f32>> = fetched
+            .iter()
+            .map(|transform| transform.as_ref().map(|transform| transform.x))
+            .collect();
+        assert_eq!(xs, vec![Some(2.0), None, Some(0.0), Some(2.0)]);
+        // The duplicate only shares a borrow, but every borrow blocks writers until dropped
+        assert!(storage
+            .try_get_entity_component_mut::<Transform>(&entities[2])
+            .is_err());
+        drop(fetched);
+        assert!(storage
+            .get_entity_component_mut::<Transform>(&entities[2])
+            .is_some());
+    }
+
+    #[test]
+    fn chunk_totals_add_up_to_the_column_total() {
+        let mut storage = ComponentStorage::new();
+        storage.spawn_batch(10, |i| (Transform { x: i as f32 },));
+        let chunks = storage.chunks_mut::<Transform>(3);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks.iter().map(|chunk| chunk.len()).sum::<usize>(), 10);
+        let total: f32 = chunks
+            .into_iter()
+            .map(|chunk| chunk.iter_mut().map(|cell| cell.get_mut().x).sum::<f32>())
+            .sum();
+        assert_eq!(total, 45.0);
+        assert!(storage.chunks_mut::<Mesh>(3).is_empty());
+        storage.ensure_column::<Mesh>();
+        assert!(storage
+            .chunks_mut::<Mesh>(0)
+            .iter()
+            .all(|chunk| chunk.is_empty()));
+    }
+
+    #[test]
+    fn weak_entity_stops_resolving_once_despawned() {
+        let mut storage = ComponentStorage::new();
+        let entity = storage.create_entity();
+        let weak = storage.downgrade(&entity).unwrap();
+        assert_eq!(storage.resolve(&weak), Some(entity.clone()));
+        storage.remove_entity(entity.clone());
+        assert_eq!(storage.resolve(&weak), None);
+        // Bringing the id back doesn't revive the old reference
+        storage.create_entity_with_id(entity.0);
+        assert_eq!(storage.resolve(&weak), None);
+        assert!(storage.downgrade(&Entity(50)).is_none());
+    }
+
+    #[test]
+    fn fixed_growth_adds_the_configured_step() {
+        let mut storage = ComponentStorage::new();
+        storage.set_growth_policy::<Transform>(GrowthPolicy::Fixed(10));
+        let mut capacities = vec![];
+        for i in 0..25 {
+            storage.spawn((Transform { x: i as f32 },));
+            capacities.push(storage.get_component_vec::<Transform>().unwrap().capacity());
+        }
+        assert_eq!(capacities[0], 10);
+        assert_eq!(capacities[9], 10);
+        assert_eq!(capacities[10], 20);
+        assert_eq!(capacities[24], 30);
+    }
+
+    #[test]
+    fn adding_then_removing_emits_two_changes() {
+        let mut storage = ComponentStorage::new();
+        let entity = storage.create_entity();
+        storage.register_component(&entity, Mesh::default());
+        storage.register_component(&entity, Mesh::default());
+        storage.remove_component::<Mesh>(&entity);
+        let mesh = TypeId::of::<Mesh>();
+        assert_eq!(
+            storage.drain_archetype_changes(),
+            vec![
+                ArchetypeChanged {
+                    entity: entity.clone(),
+                    added: vec![mesh],
+                    removed: vec![],
+                },
+                ArchetypeChanged {
+                    entity: entity.clone(),
+                    added: vec![],
+                    removed: vec![mesh],
+                },
+            ]
+        );
+        storage.remove_entity(entity.clone());
+        storage.remove_entity(entity);
+        assert_eq!(storage.drain_archetype_changes().len(), 1);
+    }
+
+    #[test]
+    fn get_with_slot_matches_the_component_id() {
+        let mut storage = ComponentStorage::new();
+        storage.spawn((Mesh::default(),));
+        let entity = storage.spawn((Mesh { label: "x".into() },));
+        storage.remove_component::<Mesh>(&Entity(0));
+        let (slot, mesh) = storage.get_with_slot::<Mesh>(&entity).unwrap();
+        assert_eq!(Some(slot), storage.get_entity_component_id::<Mesh>(&entity));
+        assert_eq!(slot, 0);
+        assert_eq!(mesh.label, "x");
+        assert!(storage.get_with_slot::<Mesh>(&Entity(0)).is_none());
+    }
+
+    #[test]
+    fn setup_runs_once_for_a_component_added_after_the_first_tick() {
+        let mut storage = ComponentStorage::new();
+        let a = storage.spawn((SetupCount::default(),));
+        let b = storage.spawn((SetupCount::default(),));
+        storage.setup_components();
+        let c = storage.spawn((SetupCount::default(),));
+        storage.setup_components();
+        storage.setup_components();
+        for entity in [&a, &b, &c] {
+            assert_eq!(
+                storage
+                    .get_entity_component::<SetupCount>(entity)
+                    .unwrap()
+                    .n,
+                1
+            );
+        }
+
+        // Flags follow their slots through a swap_remove and a sort
+        storage.remove_component::<SetupCount>(&a);
+        let d = storage.spawn((SetupCount::default(),));
+        storage.sort_component::<SetupCount, u32>(|component| 10 - component.n);
+        storage.setup_components();
+        for entity in [&b, &c, &d] {
+            assert_eq!(
+                storage
+                    .get_entity_component::<SetupCount>(entity)
+                    .unwrap()
+                    .n,
+                1
+            );
+        }
+    }
+
+    #[test]
+    fn run_calls_back_once_per_frame() {
+        let mut storage = ComponentStorage::default();
+        let entity = storage.spawn((Counter::default(), SetupCount::default()));
+        let mut calls = 0;
+        storage.run(5, |_| calls += 1);
+        assert_eq!(calls, 5);
+        assert_eq!(
+            storage.get_entity_component::<Counter>(&entity).unwrap().n,
+            5
+        );
+        assert_eq!(
+            storage
+                .get_entity_component::<SetupCount>(&entity)
+                .unwrap()
+                .n,
+            1
+        );
+    }
+
+    #[test]
+    fn cloned_entity_gets_equal_independent_components() {
+        let mut storage = ComponentStorage::new();
+        storage.register_cloneable::<Transform>();
+        storage.register_cloneable::<Mesh>();
+        let source = storage.spawn((
+            Transform { x: 4.0 },
+            Mesh {
+                label: "prefab".into(),
+            },
+            Counter::default(),
+        ));
+        let copy = storage.clone_entity(&source);
+        assert_ne!(copy, source);
+        assert_eq!(
+            *storage.get_entity_component::<Transform>(&copy).unwrap(),
+            Transform { x: 4.0 }
+        );
+        assert_eq!(
+            storage.get_entity_component::<Mesh>(&copy).unwrap().label,
+            "prefab"
+        );
+        // Counter was never registered as cloneable
+        assert!(storage.get_entity_component::<Counter>(&copy).is_none());
+
+        storage
+            .get_entity_component_mut::<Transform>(&copy)
+            .unwrap()
+            .x = 1.0;
+        storage
+            .get_entity_component_mut::<Mesh>(&copy)
+            .unwrap()
+            .label = "copy".into();
+        assert_eq!(
+            storage
+                .get_entity_component::<Transform>(&source)
+                .unwrap()
+                .x,
+            4.0
+        );
+        assert_eq!(
+            storage.get_entity_component::<Mesh>(&source).unwrap().label,
+            "prefab"
+        );
+    }
+
+    #[test]
+    fn get_or_default_covers_present_absent_and_dead() {
+        let mut storage = ComponentStorage::new();
+        let present = storage.spawn((Transform { x: 4.0 },));
+        let absent = storage.create_entity();
+        let dead = storage.spawn((Transform { x: 2.0 },));
+        storage.remove_entity(dead.clone());
+        assert_eq!(storage.get_or_default::<Transform>(&present).x, 4.0);
+        assert_eq!(
+            storage.get_or_default::<Transform>(&absent),
+            Transform::default()
+        );
+        assert_eq!(
+            storage.get_or_default::<Transform>(&dead),
+            Transform::default()
+        );
+        assert_eq!(storage.get_or_default::<Mesh>(&Entity(42)), Mesh::default());
+    }
+
+    #[test]
+    fn shrink_releases_capacity_after_a_level_unloads() {
+        let mut storage = ComponentStorage::new();
+        let entities = storage.spawn_batch(1000, |i| (Transform { x: i as f32 },));
+        for entity in &entities {
+            storage.remove_component::<Transform>(entity);
+            storage.remove_entity(entity.clone());
+        }
+        let before = storage.get_component_vec::<Transform>().unwrap().capacity();
+        let alive_before = storage.alive_entities.capacity();
+        storage.shrink_to_fit();
+        assert!(before >= 1000);
+        assert_eq!(
+            storage.get_component_vec::<Transform>().unwrap().capacity(),
+            0
+        );
+        assert!(storage.alive_entities.capacity() < alive_before);
+    }
+
+    #[test]
+    fn query_results_sort_by_entity_index() {
+        let mut storage = ComponentStorage::new();
+        for id in [7, 2, 9, 4] {
+            let entity = storage.create_entity_with_id(id).unwrap();
+            storage.register_component(&entity, Transform { x: 1.0 });
+        }
+        let mut results = storage.collect_query::<(Transform,)>();
+        // Every x ties, the index alone decides the order
+        results.sort_by(|a, b| a.1.x.total_cmp(&b.1.x).then(a.0.index().cmp(&b.0.index())));
+        let indices: Vec<u32> = results.iter().map(|(entity, _)| entity.index()).collect();
+        assert_eq!(indices, [2, 4, 7, 9]);
+        assert_eq!(Entity(5).index(), 5);
+    }
+
+    #[test]
+    fn checked_out_component_keeps_its_slot() {
+        struct External<'a>(&'a mut u32);
+
+        let mut storage = ComponentStorage::new();
+        let entity = storage.spawn((SetupCount::default(),));
+        storage.setup_components();
+        storage.drain_archetype_changes();
+        let handle = storage.component_handle::<SetupCount>(&entity).unwrap();
+        let slot = storage.get_entity_component_id::<SetupCount>(&entity);
+        let version = storage.component_version::<SetupCount>(&entity).unwrap();
+
+        let mut component = storage.take_component::<SetupCount>(&entity).unwrap();
+        assert_eq!(component.n, 1);
+        {
+            let external = External(&mut component.n);
+            *external.0 += 10;
+        }
+        // The slot now holds a default, which is a change of its own
+        let taken = storage.component_version::<SetupCount>(&entity).unwrap();
+        assert!(taken > version);
+        storage.put_component(&entity, component);
+
+        storage.setup_components();
+        assert_eq!(handle.resolve(&storage).unwrap().n, 11);
+        assert_eq!(storage.get_entity_component_id::<SetupCount>(&entity), slot);
+        assert!(storage.component_version::<SetupCount>(&entity).unwrap() > taken);
+        assert!(storage.drain_archetype_changes().is_empty());
+
+        let shared = storage.create_entity();
+        storage.share_component::<SetupCount>(&entity, &shared);
+        assert!(storage.take_component::<SetupCount>(&shared).is_none());
+        assert!(storage.take_component::<Mesh>(&entity).is_none());
+    }
+
+    #[test]
+    fn swapped_transforms_read_each_others_values() {
+        let mut storage = ComponentStorage::new();
+        let a = storage.spawn((Transform { x: 1.0 }, Counter::default()));
+        let b = storage.spawn((Transform { x: 2.0 },));
+        let a_slot = storage.get_entity_component_id::<Transform>(&a);
+        let versions = [
+            storage.component_version::<Transform>(&a).unwrap(),
+            storage.component_version::<Transform>(&b).unwrap(),
+        ];
+        storage.setup_components();
+        storage.swap_components::<Transform>(&a, &b);
+        assert!(storage.component_version::<Transform>(&a).unwrap() > versions[0]);
+        assert!(storage.component_version::<Transform>(&b).unwrap() > versions[1]);
+        assert_eq!(
+            storage.get_entity_component::<Transform>(&a).unwrap().x,
+            2.0
+        );
+        assert_eq!(
+            storage.get_entity_component::<Transform>(&b).unwrap().x,
+            1.0
+        );
+        assert_eq!(storage.get_entity_component_id::<Transform>(&a), a_slot);
+
+        // Nothing happens unless both entities hold the type
+        storage.swap_components::<Counter>(&a, &b);
+        assert!(storage.get_entity_component::<Counter>(&a).is_some());
+        assert_eq!(storage.validate_integrity(), Ok(()));
+    }
+
+    #[test]
+    fn two_clients_despawning_in_different_orders_agree() {
+        let client = || {
+            let mut storage = ComponentStorage::new();
+            storage.spawn_batch(6, |i| {
+                (
+                    Transform { x: i as f32 },
+                    Mesh::default(),
+                    Counter::default(),
+                    SetupCount::default(),
+                    Tag::<0>,
+                    Tag::<1>,
+                )
+            });
+            storage.drain_archetype_changes();
+            storage
+        };
+        let mut first = client();
+        let mut second = client();
+        first.despawn_batch(&[Entity(4), Entity(1), Entity(2), Entity(1)]);
+        second.despawn_batch(&[Entity(2), Entity(4), Entity(1)]);
+
+        assert_eq!(first.free_slots(), [1, 2, 4]);
+        assert_eq!(first.free_slots(), second.free_slots());
+        assert_eq!(first.get_entities(), second.get_entities());
+        let changes = first.drain_archetype_changes();
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes, second.drain_archetype_changes());
+        assert_eq!(first.create_entity(), second.create_entity());
+    }
+
+    #[test]
+    fn update_reads_the_frame_number_from_the_context() {
+        struct FrameContext {
+            frame: u32,
+        }
+
+        #[derive(PartialEq)]
+        struct LastFrame(Option<u32>);
+
+        impl Component for LastFrame {
+            fn setup(&mut self, _: &ComponentStorage) {}
+            fn update(&mut self, _: &ComponentStorage) {}
+            fn update_with_context(&mut self, _: &ComponentStorage, context: &mut dyn Any) {
+                self.0 = context
+                    .downcast_mut::<FrameContext>()
+                    .map(|context| context.frame);
+            }
+        }
+
+        let mut storage = ComponentStorage::new();
+        let entity = storage.spawn((LastFrame(None), Counter::default()));
+        storage.update_components_with(&mut FrameContext { frame: 41 });
+        storage.update_components_with(&mut FrameContext { frame: 42 });
+        assert_eq!(
+            storage
+                .get_entity_component::<LastFrame>(&entity)
+                .unwrap()
+                .0,
+            Some(42)
+        );
+        // Components that don't override it fall back to `update`
+        assert_eq!(
+            storage.get_entity_component::<Counter>(&entity).unwrap().n,
+            2
+        );
+        storage.update_components_with(&mut ());
+        assert_eq!(
+            storage
+                .get_entity_component::<LastFrame>(&entity)
+                .unwrap()
+                .0,
+            None
+        );
+    }
+
+    #[test]
+    fn swapped_worlds_exchange_front_and_back() {
+        let mut front = ComponentStorage::new();
+        let mut back = ComponentStorage::new();
+        for x in [1.0, 2.0] {
+            front.spawn((Transform { x },));
+            back.spawn((Transform { x: x * 10.0 },));
+        }
+        back.spawn((Mesh::default(),));
+        front.swap(&mut back);
+
+        assert_eq!(front.alive_count(), 3);
+        assert_eq!(back.alive_count(), 2);
+        // Spawned in lockstep, the same ids name the same entities in both buffers
+        for entity in back.get_entities() {
+            assert_eq!(
+                front.get_entity_component::<Transform>(&entity).unwrap().x,
+                back.get_entity_component::<Transform>(&entity).unwrap().x * 10.0
+            );
+        }
+        assert!(front.get_entity_component::<Mesh>(&Entity(2)).is_some());
+        assert!(!back.is_registered::<Mesh>());
+    }
+
+    #[test]
+    fn ensure_column_twice_leaves_one_column() {
+        let mut storage = ComponentStorage::new();
+        storage.ensure_column::<Transform>();
+        storage.ensure_column::<Transform>();
+        assert!(storage.is_registered::<Transform>());
+        assert_eq!(storage.component_vectors.len(), 1);
+        assert_eq!(storage.get_component_vec::<Transform>().unwrap().len(), 0);
+        assert_eq!(storage.component_pairs::<Transform>().count(), 0);
+
+        let entity = storage.spawn((Transform { x: 1.0 },));
+        storage.ensure_column::<Transform>();
+        assert_eq!(storage.component_vectors.len(), 1);
+        assert_eq!(
+            storage
+                .get_entity_component::<Transform>(&entity)
+                .unwrap()
+                .x,
+            1.0
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "instrumentation")]
+    fn access_counts_match_and_reset_in_every_pass() {
+        let mut storage = ComponentStorage::new();
+        let entity = storage.spawn((Transform::default(), Mesh::default()));
+        let stats = |storage: &ComponentStorage| {
+            let mut stats = storage.access_stats();
+            stats.retain(|&(_, reads, writes)| reads + writes > 0);
+            stats.sort_by_key(|&(_, reads, _)| reads);
+            stats
+        };
+        let touch = |storage: &ComponentStorage| {
+            for _ in 0..3 {
+                storage.get_entity_component::<Transform>(&entity);
+            }
+            for _ in 0..2 {
+                storage.get_entity_component_mut::<Transform>(&entity);
+            }
+            storage.get_entity_component::<Mesh>(&entity);
+        };
+        let expected = [
+            (TypeId::of::<Mesh>(), 1, 0),
+            (TypeId::of::<Transform>(), 3, 2),
+        ];
+
+        touch(&storage);
+        assert_eq!(stats(&storage), expected);
+        storage.update_components();
+        assert!(stats(&storage).is_empty());
+        touch(&storage);
+        storage.update_components_mut();
+        assert!(stats(&storage).is_empty());
+        touch(&storage);
+        touch(&storage);
+        assert_eq!(
+            stats(&storage),
+            [
+                (TypeId::of::<Mesh>(), 2, 0),
+                (TypeId::of::<Transform>(), 6, 4),
+            ]
+        );
+        storage.update_components_with(&mut ());
+        assert!(stats(&storage).is_empty());
+    }
+
+    #[test]
+    fn interval_3_updates_on_frames_0_3_and_6() {
+        #[derive(PartialEq)]
+        struct Frames(Vec<u64>);
+
+        impl Component for Frames {
+            fn setup(&mut self, _: &ComponentStorage) {}
+            fn update(&mut self, world: &ComponentStorage) {
+                self.0.push(world.current_frame());
+            }
+        }
+
+        let mut storage = ComponentStorage::new();
+        let entity = storage.spawn((Frames(vec![]), Counter::default()));
+        storage.set_update_interval::<Frames>(3);
+        for _ in 0..7 {
+            storage.update_components();
+        }
+        assert_eq!(
+            storage.get_entity_component::<Frames>(&entity).unwrap().0,
+            [0, 3, 6]
+        );
+        assert_eq!(
+            storage.get_entity_component::<Counter>(&entity).unwrap().n,
+            7
+        );
+    }
+
+    #[test]
+    fn type_is_registered_once_inserted() {
+        let mut storage = ComponentStorage::new();
+        let entity = storage.create_entity();
+        assert!(!storage.is_registered::<Transform>());
+        assert!(storage.get_entity_component::<Transform>(&entity).is_none());
+        assert!(!storage.is_registered::<Transform>());
+        storage.register_component(&entity, Transform::default());
+        assert!(storage.is_registered::<Transform>());
+        assert!(!storage.is_registered::<Mesh>());
+        // The column outlives the last component in it
+        storage.remove_component::<Transform>(&entity);
+        assert!(storage.is_registered::<Transform>());
+    }
+
+    #[test]
+    fn raw_pointers_agree_with_the_safe_getters() {
+        let mut storage = ComponentStorage::new();
+        storage.spawn((Transform { x: 1.0 },));
+        let entity = storage.spawn((Transform { x: 2.5 },));
+        let read = storage.raw_component_ptr::<Transform>(&entity).unwrap();
+        // Safe, nothing else borrows the component and the storage isn't changed meanwhile
+        assert_eq!(
+            unsafe { &*read },
+            &*storage.get_entity_component::<Transform>(&entity).unwrap()
+        );
+        let write = storage.raw_component_ptr_mut::<Transform>(&entity).unwrap();
+        unsafe { (*write).x = 4.0 };
+        assert_eq!(
+            storage
+                .get_entity_component::<Transform>(&entity)
+                .unwrap()
+                .x,
+            4.0
+        );
+        let (slot, _) = storage.get_with_slot::<Transform>(&entity).unwrap();
+        assert_eq!(slot, 1);
+        assert!(storage.raw_component_ptr::<Mesh>(&entity).is_none());
+    }
+
+    #[test]
+    fn auto_attach_component_appears_on_every_new_entity() {
+        #[derive(Default, PartialEq)]
+        struct Health(u32);
+
+        impl Component for Health {
+            const HAS_BEHAVIOR: bool = false;
+            const AUTO_ATTACH: bool = true;
+            fn setup(&mut self, _: &ComponentStorage) {}
+            fn update(&mut self, _: &ComponentStorage) {}
+        }
+
+        let mut storage = ComponentStorage::new();
+        let before = storage.create_entity();
+        storage.register_type::<Health>();
+        storage.register_type::<Transform>();
+        let spawned = storage.spawn((Mesh::default(),));
+        let created = storage.create_entity();
+        let with_id = storage.create_entity_with_id(10).unwrap();
+        for entity in [&spawned, &created, &with_id] {
+            assert!(storage.get_entity_component::<Health>(entity).is_some());
+            assert!(storage.get_entity_component::<Transform>(entity).is_none());
+        }
+        assert!(storage.get_entity_component::<Health>(&before).is_none());
+        assert!(storage.is_registered::<Transform>());
+    }
+
+    #[test]
+    fn despawned_ids_show_up_as_free_slots() {
+        let mut storage = ComponentStorage::new();
+        let entities: Vec<Entity> = (0..4).map(|_| storage.create_entity()).collect();
+        assert!(storage.free_slots().is_empty());
+        storage.remove_entity(entities[1].clone());
+        storage.remove_entity(entities[3].clone());
+        assert_eq!(storage.free_slots(), [1, 3]);
+        assert!(storage.is_slot_free(1) && storage.is_slot_free(3));
+        assert!(!storage.is_slot_free(0));
+        // Past the high-water mark nothing has been handed out yet
+        assert!(!storage.is_slot_free(4));
+
+        storage.create_entity_with_id(7).unwrap();
+        assert_eq!(storage.free_slots(), [1, 3, 4, 5, 6]);
+        storage.create_entity_with_id(3).unwrap();
+        assert_eq!(storage.free_slots(), [1, 4, 5, 6]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn reserved_slots_are_filled_from_several_threads() {
+        let mut storage = ComponentStorage::new();
+        let slots = storage.reserve_slots::<Transform>(100);
+        assert_eq!(slots, 0..100);
+        let chunks = storage.chunks_mut::<Transform>(4);
+        assert_eq!(chunks.len(), 4);
+        std::thread::scope(|scope| {
+            for chunk in chunks {
+                scope.spawn(move || {
+                    for transform in chunk.iter_mut() {
+                        transform.get_mut().x = 1.0;
+                    }
+                });
+            }
+        });
+        for (slot, transform) in storage.chunks_mut::<Transform>(1)[0].iter_mut().enumerate() {
+            transform.get_mut().x *= slot as f32;
+        }
+
+        let entities = storage.spawn_from_slots::<Transform>(slots);
+        assert_eq!(entities.len(), 100);
+        for (slot, entity) in entities.iter().enumerate() {
+            assert_eq!(
+                storage.get_entity_component::<Transform>(entity).unwrap().x,
+                slot as f32
+            );
+        }
+    }
+
+    #[test]
+    fn only_reserved_slots_are_handed_out_and_only_once() {
+        let mut storage = ComponentStorage::new();
+        let owner = storage.spawn((Mesh { label: "a".into() }, SetupCount::default()));
+        storage.setup_components();
+        let slots = storage.reserve_slots::<Mesh>(2);
+        storage.reserve_slots::<SetupCount>(1);
+        storage.setup_components();
+        assert_eq!(
+            storage.get_component_vec::<SetupCount>().unwrap()[1]
+                .borrow()
+                .n,
+            0
+        );
+
+        let spawned = storage.spawn_from_slots::<Mesh>(0..slots.end);
+        assert_eq!(spawned.len(), 2);
+        assert_eq!(
+            storage.get_entity_component_id::<Mesh>(&spawned[0]),
+            Some(1)
+        );
+        storage
+            .get_entity_component_mut::<Mesh>(&spawned[0])
+            .unwrap()
+            .label = "b".into();
+        assert_eq!(
+            storage.get_entity_component::<Mesh>(&owner).unwrap().label,
+            "a"
+        );
+        assert!(storage.spawn_from_slots::<Mesh>(slots).is_empty());
+        assert!(storage.spawn_from_slots::<Transform>(0..1).is_empty());
+
+        // Still reserved, followed through a removal that moves it
+        storage.remove_component::<SetupCount>(&owner);
+        let late = storage.spawn_from_slots::<SetupCount>(0..1);
+        assert_eq!(late.len(), 1);
+        storage.setup_components();
+        storage.setup_components();
+        assert_eq!(
+            storage
+                .get_entity_component::<SetupCount>(&late[0])
+                .unwrap()
+                .n,
+            1
+        );
+        assert_eq!(storage.validate_integrity(), Ok(()));
+    }
+
+    #[test]
+    fn update_pass_skips_slots_still_reserved() {
+        let mut storage = ComponentStorage::new();
+        let slots = storage.reserve_slots::<Counter>(3);
+        storage.update_components();
+        storage.update_components_mut();
+        storage.update_components_with(&mut ());
+        let entities = storage.spawn_from_slots::<Counter>(slots.start..slots.start + 1);
+        storage.update_components();
+        let counts: Vec<u32> = storage
+            .get_component_vec::<Counter>()
+            .unwrap()
+            .iter()
+            .map(|counter| counter.borrow().n)
+            .collect();
+        assert_eq!(counts, [1, 0, 0]);
+        assert_eq!(
+            storage
+                .get_entity_component::<Counter>(&entities[0])
+                .unwrap()
+                .n,
+            1
+        );
+    }
+
+    #[derive(Clone, PartialEq)]
+    struct GpuBuffer {
+        despawns: Rc<Cell<u32>>,
+        updates: Rc<Cell<u32>>,
+    }
+
+    impl Component for GpuBuffer {
+        fn setup(&mut self, _: &ComponentStorage) {}
+        fn update(&mut self, _: &ComponentStorage) {
+            self.updates.set(self.updates.get() + 1);
+        }
+        fn on_despawn(&mut self, _: &Entity, _: &ComponentStorage) {
+            self.despawns.set(self.despawns.get() + 1);
+        }
+    }
+
+    #[test]
+    fn despawn_fires_on_despawn_once_and_frees_the_slot() {
+        let buffer = || GpuBuffer {
+            despawns: Rc::new(Cell::new(0)),
+            updates: Rc::new(Cell::new(0)),
+        };
+        let mut storage = ComponentStorage::new();
+        let (doomed, kept) = (buffer(), buffer());
+        let entity = storage.spawn((doomed.clone(), Transform { x: 1.0 }));
+        let other = storage.spawn((kept.clone(), Transform { x: 2.0 }));
+        storage.update_components();
+
+        storage.remove_entity(entity.clone());
+        storage.remove_entity(entity);
+        assert_eq!(doomed.despawns.get(), 1);
+        storage.update_components();
+        assert_eq!(doomed.updates.get(), 1);
+        assert_eq!(kept.updates.get(), 2);
+        assert_eq!(storage.get_component_vec::<GpuBuffer>().unwrap().len(), 1);
+        assert_eq!(storage.get_component_vec::<Transform>().unwrap().len(), 1);
+        assert_eq!(
+            storage.get_entity_component::<Transform>(&other).unwrap().x,
+            2.0
+        );
+
+        // A slot the entity shared is left to the entity still holding it
+        let sharer = storage.create_entity();
+        storage.share_component::<GpuBuffer>(&other, &sharer);
+        storage.remove_entity(sharer);
+        assert_eq!(kept.despawns.get(), 0);
+        storage.update_components();
+        assert_eq!(kept.updates.get(), 3);
+        assert_eq!(storage.validate_integrity(), Ok(()));
+    }
+
+    #[test]
+    fn exactly_one_of_rejects_two_of_the_exclusive_set() {
+        let mut storage = ComponentStorage::new();
+        let exclusive = [TypeId::of::<Counter>(), TypeId::of::<SetupCount>()];
+        let one = storage.spawn((Counter::default(), Transform::default()));
+        let both = storage.spawn((Counter::default(), SetupCount::default()));
+        let none = storage.spawn((Transform::default(),));
+        assert!(storage.exactly_one_of(&one, &exclusive));
+        assert!(!storage.exactly_one_of(&both, &exclusive));
+        assert!(!storage.exactly_one_of(&none, &exclusive));
+        storage.remove_entity(one.clone());
+        assert!(!storage.exactly_one_of(&one, &exclusive));
+    }
+
+    #[test]
+    fn first_insert_creates_a_column_with_the_initial_capacity() {
+        let mut storage = ComponentStorage::new();
+        let entity = storage.spawn((Mesh::default(),));
+        storage.set_initial_capacity::<Transform>(256);
+        storage.set_initial_capacity::<Mesh>(256);
+        storage.register_component(&entity, Transform::default());
+        assert!(storage.get_component_vec::<Transform>().unwrap().capacity() >= 256);
+        // Mesh already had a column, it keeps its own capacity
+        assert!(storage.get_component_vec::<Mesh>().unwrap().capacity() < 256);
+
+        storage.set_initial_capacity::<Counter>(64);
+        storage.ensure_column::<Counter>();
+        assert!(storage.get_component_vec::<Counter>().unwrap().capacity() >= 64);
+    }
+
+    #[test]
+    fn update_components_mut_updates_everything_despite_outstanding_borrows() {
+        #[derive(PartialEq)]
+        struct Peeker(u32);
+
+        // Borrows its own column while being updated, through `update_components` that would
+        // run into its own `RefMut` and panic
+        impl Component for Peeker {
+            fn setup(&mut self, _: &ComponentStorage) {}
+            fn update(&mut self, world: &ComponentStorage) {
+                let peers: Vec<_> = world.component_pairs::<Peeker>().collect();
+                let counters: Vec<_> = world.component_pairs::<Counter>().collect();
+                self.0 += peers.len() as u32 + counters.len() as u32;
+            }
+        }
+
+        let mut storage = ComponentStorage::new();
+        let entities = storage.spawn_batch(3, |_| (Counter::default(), Peeker(0)));
+        storage.update_components_mut();
+        storage.update_components_mut();
+        for entity in entities.iter() {
+            assert_eq!(
+                storage.get_entity_component::<Counter>(entity).unwrap().n,
+                2
+            );
+            assert_eq!(storage.get_entity_component::<Peeker>(entity).unwrap().0, 6);
+        }
+    }
+
+    #[test]
+    fn ordered_removal_keeps_the_column_in_order() {
+        let mut storage = ComponentStorage::new();
+        let entities = storage.spawn_batch(5, |i| (Transform { x: i as f32 },));
+        let removed = storage.remove_component_ordered::<Transform>(&entities[2]);
+        assert_eq!(removed, Some(Transform { x: 2.0 }));
+        let column: Vec<f32> = storage
+            .get_component_vec::<Transform>()
+            .unwrap()
+            .iter()
+            .map(|transform| transform.borrow().x)
+            .collect();
+        assert_eq!(column, [0.0, 1.0, 3.0, 4.0]);
+        for (i, entity) in entities.iter().enumerate() {
+            let x = storage
+                .get_entity_component::<Transform>(entity)
+                .map(|transform| transform.x);
+            assert_eq!(x, (i != 2).then_some(i as f32));
+        }
+        assert_eq!(
+            storage.get_entity_component_id::<Transform>(&entities[4]),
+            Some(3)
+        );
+        assert_eq!(
+            storage.remove_component_ordered::<Transform>(&entities[2]),
+            None
+        );
+        assert_eq!(storage.validate_integrity(), Ok(()));
+    }
+
+    #[test]
+    fn entity_seed_depends_only_on_the_id_and_world_seed() {
+        let mut storage = ComponentStorage::new();
+        let entities = storage.spawn_batch(3, |_| (Transform::default(),));
+        let other = ComponentStorage::new();
+        for entity in entities.iter() {
+            let seed = storage.entity_seed(entity, 42);
+            assert_eq!(storage.entity_seed(entity, 42), seed);
+            assert_eq!(other.entity_seed(entity, 42), seed);
+            assert_ne!(storage.entity_seed(entity, 43), seed);
+        }
+        assert_ne!(
+            storage.entity_seed(&entities[0], 42),
+            storage.entity_seed(&entities[1], 42)
+        );
+    }
+
+    // `Reader` sorts before `Writer` by type name, so only a declared ordering runs `Writer` first
+    #[derive(Debug, Default, PartialEq)]
+    struct Writer(Rc<Cell<u32>>);
+
+    impl Component for Writer {
+        fn setup(&mut self, _: &ComponentStorage) {
+            self.0.set(7);
+        }
+        fn update(&mut self, _: &ComponentStorage) {}
+    }
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Reader {
+        shared: Rc<Cell<u32>>,
+        seen: u32,
+    }
+
+    impl Component for Reader {
+        fn setup(&mut self, _: &ComponentStorage) {
+            self.seen = self.shared.get();
+        }
+        fn update(&mut self, _: &ComponentStorage) {}
+    }
+
+    fn setup_world(ordered: bool) -> u32 {
+        let mut storage = ComponentStorage::new();
+        let shared = Rc::new(Cell::new(0));
+        let entity = storage.spawn((
+            Reader {
+                shared: shared.clone(),
+                seen: 0,
+            },
+            Writer(shared),
+        ));
+        if ordered {
+            storage.order_setup::<Writer, Reader>();
+        }
+        storage.setup_components();
+        let seen = storage
+            .get_entity_component::<Reader>(&entity)
+            .unwrap()
+            .seen;
+        seen
+    }
+
+    #[test]
+    fn setup_respects_declared_ordering() {
+        assert_eq!(setup_world(false), 0);
+        assert_eq!(setup_world(true), 7);
+    }
+
+    #[test]
+    fn every_update_pass_advances_the_frame_by_one() {
+        let mut storage = ComponentStorage::new();
+        storage.spawn((Counter::default(),));
+        assert_eq!(storage.current_frame(), 0);
+        storage.update_components();
+        assert_eq!(storage.current_frame(), 1);
+        storage.update_components_mut();
+        assert_eq!(storage.current_frame(), 2);
+        storage.update_components_with(&mut ());
+        assert_eq!(storage.current_frame(), 3);
+        storage.run(4, |_| {});
+        assert_eq!(storage.current_frame(), 7);
+        storage.setup_components();
+        assert_eq!(storage.current_frame(), 7);
+    }
+}
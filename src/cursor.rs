@@ -0,0 +1,101 @@
+use core::{
+    any::TypeId,
+    cell::{Ref, RefMut},
+    marker::PhantomData,
+};
+
+use crate::component::{Component, ComponentStorage, Entity};
+
+// Steps back and forth over the alive entities holding `T`, starting before the first one
+pub struct ComponentCursor<'a, T> {
+    storage: &'a ComponentStorage,
+    position: Option<usize>,
+    marker: PhantomData<T>,
+}
+
+impl<T: Component + 'static> ComponentCursor<'_, T> {
+    fn holds_component(&self, position: usize) -> bool {
+        let entity = &self.storage.alive_entities()[position];
+        self.storage.has_component_type(entity, TypeId::of::<T>())
+    }
+
+    // Stays on the current entity when there is nothing before it
+    pub fn prev(&mut self) -> Option<Entity> {
+        let position = self.position?;
+        let previous = (0..position)
+            .rev()
+            .find(|&position| self.holds_component(position))?;
+        self.position = Some(previous);
+        self.current_entity()
+    }
+
+    pub fn current_entity(&self) -> Option<Entity> {
+        let position = self.position?;
+        self.storage.alive_entities().get(position).cloned()
+    }
+
+    pub fn current(&self) -> Option<Ref<'_, T>> {
+        self.storage
+            .get_entity_component::<T>(&self.current_entity()?)
+    }
+
+    pub fn current_mut(&self) -> Option<RefMut<'_, T>> {
+        self.storage
+            .get_entity_component_mut::<T>(&self.current_entity()?)
+    }
+}
+
+// Stays on the current entity when there is nothing after it
+impl<T: Component + 'static> Iterator for ComponentCursor<'_, T> {
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Entity> {
+        let start = self.position.map_or(0, |position| position + 1);
+        let next = (start..self.storage.alive_entities().len())
+            .find(|&position| self.holds_component(position))?;
+        self.position = Some(next);
+        self.current_entity()
+    }
+}
+
+impl ComponentStorage {
+    pub fn cursor<T: Component + 'static>(&self) -> ComponentCursor<'_, T> {
+        ComponentCursor {
+            storage: self,
+            position: None,
+            marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Mesh, Transform};
+
+    #[test]
+    fn cursor_steps_forward_and_back_and_edits_in_place() {
+        let mut storage = ComponentStorage::new();
+        let a = storage.spawn((Transform { x: 1.0 },));
+        storage.spawn((Mesh::default(),));
+        let b = storage.spawn((Transform { x: 2.0 },));
+        let c = storage.spawn((Transform { x: 3.0 },));
+
+        let mut cursor = storage.cursor::<Transform>();
+        assert!(cursor.current_entity().is_none() && cursor.prev().is_none());
+        assert_eq!(cursor.next(), Some(a.clone()));
+        assert_eq!(cursor.next(), Some(b.clone()));
+        assert_eq!(cursor.next(), Some(c.clone()));
+        assert_eq!(cursor.next(), None);
+        assert_eq!(cursor.current_entity(), Some(c));
+        assert_eq!(cursor.prev(), Some(b.clone()));
+        cursor.current_mut().unwrap().x = 20.0;
+        assert_eq!(cursor.prev(), Some(a.clone()));
+        assert_eq!(cursor.prev(), None);
+        assert_eq!(cursor.current().unwrap().x, 1.0);
+        assert_eq!(
+            storage.get_entity_component::<Transform>(&b).unwrap().x,
+            20.0
+        );
+    }
+}
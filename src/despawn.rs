@@ -0,0 +1,111 @@
+use alloc::{rc::Rc, vec::Vec};
+use core::cell::RefCell;
+
+use crate::{
+    bundle::Bundle,
+    component::{ComponentStorage, Entity},
+};
+
+// Queues its entity for removal when dropped, the storage applies the queue in `flush_despawns`
+pub struct DespawnGuard {
+    entity: Option<Entity>,
+    queue: Rc<RefCell<Vec<Entity>>>,
+}
+
+impl DespawnGuard {
+    pub fn entity(&self) -> Option<&Entity> {
+        self.entity.as_ref()
+    }
+
+    pub fn keep(mut self) -> Entity {
+        self.entity.take().unwrap()
+    }
+}
+
+impl Drop for DespawnGuard {
+    fn drop(&mut self) {
+        if let Some(entity) = self.entity.take() {
+            self.queue.borrow_mut().push(entity);
+        }
+    }
+}
+
+impl ComponentStorage {
+    pub fn spawn_scoped<B: Bundle>(&mut self, bundle: B) -> (Entity, DespawnGuard) {
+        let entity = self.spawn(bundle);
+        let guard = DespawnGuard {
+            entity: Some(entity.clone()),
+            queue: self.despawn_queue.clone(),
+        };
+        (entity, guard)
+    }
+
+    // The entity stays untouched until `frames` more update passes have run, then goes with
+    // the next `flush_despawns`. Scheduling it again replaces the earlier delay
+    pub fn despawn_after(&mut self, entity: &Entity, frames: u32) {
+        let due = self.current_frame() + frames as u64;
+        self.despawn_timers
+            .retain(|(scheduled, _)| scheduled != entity);
+        self.despawn_timers.push((entity.clone(), due));
+    }
+
+    pub fn flush_despawns(&mut self) {
+        let mut queued = core::mem::take(&mut *self.despawn_queue.borrow_mut());
+        let frame = self.current_frame();
+        self.despawn_timers.retain(|(entity, due)| {
+            if *due > frame {
+                return true;
+            }
+            queued.push(entity.clone());
+            false
+        });
+        self.despawn_batch(&queued);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Counter, Transform};
+
+    #[test]
+    fn dropped_guard_despawns_its_entity_unless_kept() {
+        let mut storage = ComponentStorage::new();
+        let (effect, guard) = storage.spawn_scoped((Transform::default(),));
+        let (kept, keeper) = storage.spawn_scoped((Transform::default(),));
+        assert_eq!(guard.entity(), Some(&effect));
+        assert_eq!(keeper.keep(), kept);
+        drop(guard);
+        assert!(storage.is_alive(&effect));
+        storage.flush_despawns();
+        assert!(!storage.is_alive(&effect));
+        assert!(storage.get_entity_component::<Transform>(&effect).is_none());
+        assert!(storage.is_alive(&kept));
+    }
+
+    #[test]
+    fn despawn_after_keeps_the_entity_until_its_delay_runs_out() {
+        let mut storage = ComponentStorage::new();
+        let dying = storage.spawn((Counter::default(),));
+        let survivor = storage.spawn((Counter::default(),));
+        storage.despawn_after(&dying, 2);
+
+        storage.run(1, |_| {});
+        assert!(storage.is_alive(&dying));
+        assert_eq!(
+            storage.get_entity_component::<Counter>(&dying).unwrap().n,
+            1
+        );
+
+        storage.run(2, |_| {});
+        assert!(!storage.is_alive(&dying));
+        assert!(storage.get_entity_component::<Counter>(&dying).is_none());
+        assert_eq!(
+            storage
+                .get_entity_component::<Counter>(&survivor)
+                .unwrap()
+                .n,
+            3
+        );
+    }
+}
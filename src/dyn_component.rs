@@ -0,0 +1,103 @@
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    component::{ComponentStorage, Entity},
+    Map,
+};
+
+// Components whose types only exist at runtime, stored as raw bytes under a type name.
+// The layout is just the byte size declared in `register_dyn_type`
+#[derive(Clone, Default)]
+pub(crate) struct DynComponents {
+    sizes: Map<String, usize>,
+    blobs: Map<String, Map<Entity, Vec<u8>>>,
+}
+
+impl ComponentStorage {
+    pub fn register_dyn_type(&mut self, name: &str, size: usize) {
+        self.dyn_components.sizes.insert(name.into(), size);
+    }
+
+    // Returns false without storing anything if the entity is dead, the type wasn't
+    // registered or `bytes` doesn't match its declared size
+    pub fn register_dyn_component(&mut self, entity: &Entity, name: &str, bytes: Vec<u8>) -> bool {
+        if !self.is_alive(entity) || self.dyn_components.sizes.get(name) != Some(&bytes.len()) {
+            return false;
+        }
+        self.dyn_components
+            .blobs
+            .entry(name.into())
+            .or_default()
+            .insert(entity.clone(), bytes);
+        true
+    }
+
+    pub fn get_dyn_component(&self, entity: &Entity, name: &str) -> Option<&[u8]> {
+        self.dyn_components
+            .blobs
+            .get(name)?
+            .get(entity)
+            .map(Vec::as_slice)
+    }
+
+    pub fn get_dyn_component_mut(&mut self, entity: &Entity, name: &str) -> Option<&mut [u8]> {
+        self.dyn_components
+            .blobs
+            .get_mut(name)?
+            .get_mut(entity)
+            .map(Vec::as_mut_slice)
+    }
+
+    pub fn remove_dyn_component(&mut self, entity: &Entity, name: &str) -> Option<Vec<u8>> {
+        self.dyn_components.blobs.get_mut(name)?.remove(entity)
+    }
+
+    pub(crate) fn merge_dyn_components(
+        &mut self,
+        other: DynComponents,
+        remap: &dyn Fn(Entity) -> Entity,
+    ) {
+        for (name, size) in other.sizes {
+            self.dyn_components.sizes.entry(name).or_insert(size);
+        }
+        for (name, blobs) in other.blobs {
+            for (entity, bytes) in blobs {
+                self.register_dyn_component(&remap(entity), &name, bytes);
+            }
+        }
+    }
+
+    pub(crate) fn remove_dyn_components(&mut self, entity: &Entity) {
+        for blobs in self.dyn_components.blobs.values_mut() {
+            blobs.remove(entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn blob_component_round_trips() {
+        let mut storage = ComponentStorage::new();
+        let entity = storage.create_entity();
+        storage.register_dyn_type("health", 4);
+        let bytes = 75u32.to_le_bytes().to_vec();
+        assert!(!storage.register_dyn_component(&entity, "mana", bytes.clone()));
+        assert!(!storage.register_dyn_component(&entity, "health", vec![1, 2]));
+        assert!(storage.register_dyn_component(&entity, "health", bytes.clone()));
+        assert_eq!(
+            storage.get_dyn_component(&entity, "health"),
+            Some(&bytes[..])
+        );
+
+        storage.get_dyn_component_mut(&entity, "health").unwrap()[0] = 80;
+        let health = storage.get_dyn_component(&entity, "health").unwrap();
+        assert_eq!(u32::from_le_bytes(health.try_into().unwrap()), 80);
+        storage.remove_entity(entity.clone());
+        assert!(storage.get_dyn_component(&entity, "health").is_none());
+    }
+}
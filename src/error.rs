@@ -0,0 +1,216 @@
+use core::{any::TypeId, fmt};
+
+use crate::component::Entity;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ComponentError {
+    DeadEntity(Entity),
+    MissingComponent {
+        entity: Entity,
+        type_name: &'static str,
+    },
+    AlreadyBorrowed {
+        entity: Entity,
+        type_name: &'static str,
+    },
+    // Two type parameters that have to differ named the same component type
+    SameType(&'static str),
+    // Mutable access through `&self` would change every entity sharing the slot
+    SharedSlot {
+        entity: Entity,
+        type_name: &'static str,
+    },
+}
+
+impl fmt::Display for ComponentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComponentError::DeadEntity(entity) => write!(f, "entity {} is not alive", entity.0),
+            ComponentError::MissingComponent { entity, type_name } => {
+                write!(f, "entity {} has no {type_name} component", entity.0)
+            }
+            ComponentError::AlreadyBorrowed { entity, type_name } => {
+                write!(f, "{type_name} on entity {} is already borrowed", entity.0)
+            }
+            ComponentError::SameType(type_name) => {
+                write!(f, "{type_name} was given for two distinct component types")
+            }
+            ComponentError::SharedSlot { entity, type_name } => {
+                write!(
+                    f,
+                    "{type_name} on entity {} is shared with other entities",
+                    entity.0
+                )
+            }
+        }
+    }
+}
+
+impl core::error::Error for ComponentError {}
+
+// Reported by `merge` before anything is moved, so the target world is left untouched
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MergeError {
+    TargetAlive(Entity),
+    // Two entities of the merged world were remapped to this one
+    DuplicateTarget(Entity),
+    TargetAboveCap(Entity),
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeError::TargetAlive(entity) => {
+                write!(f, "merge target entity {} is already alive", entity.0)
+            }
+            MergeError::DuplicateTarget(entity) => {
+                write!(f, "more than one entity is merged into entity {}", entity.0)
+            }
+            MergeError::TargetAboveCap(entity) => {
+                write!(f, "merge target entity {} is above MAX_ENTITY_ID", entity.0)
+            }
+        }
+    }
+}
+
+impl core::error::Error for MergeError {}
+
+// Reported by `begin_transaction`, no transaction is opened
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TransactionError {
+    AlreadyOpen,
+    // A column whose type wasn't registered through `register_cloneable`
+    NotCloneable(&'static str),
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionError::AlreadyOpen => write!(f, "a transaction is already open"),
+            TransactionError::NotCloneable(type_name) => {
+                write!(f, "{type_name} isn't cloneable, so it can't be rolled back")
+            }
+        }
+    }
+}
+
+impl core::error::Error for TransactionError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IntegrityError {
+    // A live table row whose entity is missing from the alive list
+    UntrackedRow(Entity),
+    // An alive list entry without a table row
+    MissingRow(Entity),
+    MissingColumn {
+        entity: Entity,
+        type_id: TypeId,
+    },
+    InvalidSlot {
+        entity: Entity,
+        type_name: &'static str,
+        component_id: u32,
+        len: usize,
+    },
+    MaskMismatch {
+        entity: Entity,
+        expected: u64,
+        actual: u64,
+    },
+    ShareCountMismatch {
+        type_name: &'static str,
+        component_id: u32,
+        recorded: u32,
+        actual: u32,
+    },
+    // A column slot no alive entity refers to and `reserve_slots` didn't hand out
+    DanglingSlot {
+        type_name: &'static str,
+        component_id: u32,
+    },
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityError::UntrackedRow(entity) => {
+                write!(f, "entity {} has a table row but isn't alive", entity.0)
+            }
+            IntegrityError::MissingRow(entity) => {
+                write!(f, "alive entity {} has no table row", entity.0)
+            }
+            IntegrityError::MissingColumn { entity, type_id } => {
+                write!(f, "entity {} refers to {type_id:?} which has no column", entity.0)
+            }
+            IntegrityError::InvalidSlot {
+                entity,
+                type_name,
+                component_id,
+                len,
+            } => write!(
+                f,
+                "entity {} refers to {type_name} slot {component_id} but the column has {len}",
+                entity.0
+            ),
+            IntegrityError::MaskMismatch {
+                entity,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "entity {} has mask {actual:#x}, its components give {expected:#x}",
+                entity.0
+            ),
+            IntegrityError::ShareCountMismatch {
+                type_name,
+                component_id,
+                recorded,
+                actual,
+            } => write!(
+                f,
+                "{type_name} slot {component_id} is shared {recorded} times but referenced {actual} times"
+            ),
+            IntegrityError::DanglingSlot {
+                type_name,
+                component_id,
+            } => write!(f, "{type_name} slot {component_id} has no owner"),
+        }
+    }
+}
+
+impl core::error::Error for IntegrityError {}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{format, string::ToString};
+
+    use crate::{
+        component::ComponentStorage,
+        testing::{Mesh, Transform},
+    };
+
+    #[test]
+    fn messages_name_the_component_type() {
+        let mut storage = ComponentStorage::new();
+        let entity = storage.spawn((Transform::default(),));
+        let missing = storage
+            .try_get_entity_component::<Mesh>(&entity)
+            .err()
+            .unwrap();
+        assert!(missing.to_string().contains("Mesh"), "{missing}");
+
+        let _held = storage
+            .try_get_entity_component_mut::<Transform>(&entity)
+            .unwrap();
+        let borrowed = storage
+            .try_get_entity_component::<Transform>(&entity)
+            .err()
+            .unwrap();
+        assert!(borrowed.to_string().contains("Transform"), "{borrowed}");
+        assert!(
+            borrowed.to_string().contains("already borrowed"),
+            "{borrowed}"
+        );
+        assert!(format!("{storage:?}").contains("Transform"));
+    }
+}
@@ -0,0 +1,100 @@
+use alloc::{collections::VecDeque, vec::Vec};
+
+// Only valid for the channel that handed it out
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ReaderId(usize);
+
+// Every reader sees every event written after it registered, at its own pace. Events are
+// dropped once the slowest reader has read them
+pub struct EventChannel<E> {
+    events: VecDeque<E>,
+    // Sequence number of `events[0]`
+    first: u64,
+    // Sequence number of the next event each reader will read
+    cursors: Vec<u64>,
+}
+
+impl<E> Default for EventChannel<E> {
+    fn default() -> Self {
+        Self {
+            events: VecDeque::new(),
+            first: 0,
+            cursors: Vec::new(),
+        }
+    }
+}
+
+impl<E> EventChannel<E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register_reader(&mut self) -> ReaderId {
+        self.cursors.push(self.end());
+        ReaderId(self.cursors.len() - 1)
+    }
+
+    pub fn write(&mut self, event: E) {
+        self.trim();
+        self.events.push_back(event);
+    }
+
+    // Everything written since this reader's previous `read`
+    pub fn read(&mut self, reader: ReaderId) -> impl Iterator<Item = &E> + '_ {
+        self.trim();
+        let start = (self.cursors[reader.0] - self.first) as usize;
+        self.cursors[reader.0] = self.end();
+        self.events.range(start..)
+    }
+
+    // Events still held for at least one reader
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    fn end(&self) -> u64 {
+        self.first + self.events.len() as u64
+    }
+
+    fn trim(&mut self) {
+        let oldest = self.cursors.iter().copied().min().unwrap_or(self.end());
+        let consumed = (oldest - self.first) as usize;
+        self.events.drain(..consumed);
+        self.first = oldest;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readers_see_the_same_events_independently() {
+        let mut channel = EventChannel::new();
+        let first = channel.register_reader();
+        let second = channel.register_reader();
+        channel.write("hit");
+        channel.write("heal");
+
+        assert_eq!(
+            channel.read(first).copied().collect::<Vec<_>>(),
+            ["hit", "heal"]
+        );
+        assert_eq!(channel.read(first).count(), 0);
+        // Still held for the reader that hasn't caught up
+        assert_eq!(channel.len(), 2);
+        assert_eq!(
+            channel.read(second).copied().collect::<Vec<_>>(),
+            ["hit", "heal"]
+        );
+
+        channel.write("die");
+        assert_eq!(channel.len(), 1);
+        assert_eq!(channel.read(second).copied().collect::<Vec<_>>(), ["die"]);
+        assert_eq!(channel.read(first).copied().collect::<Vec<_>>(), ["die"]);
+    }
+}
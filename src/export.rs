@@ -0,0 +1,209 @@
+use crate::{
+    component::{Component, ComponentId, ComponentStorage, Entity},
+    Map,
+};
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+impl ComponentStorage {
+    // Pairs every alive entity holding `T` with a copy of it, ready to hand to a serializer
+    pub fn export_component<T: Component + Clone + 'static>(&self) -> Vec<(Entity, T)> {
+        self.alive_entities()
+            .iter()
+            .filter_map(|entity| {
+                let component = self.get_entity_component::<T>(entity)?;
+                Some((entity.clone(), component.clone()))
+            })
+            .collect()
+    }
+
+    // Entities whose `T` changed, appeared or went away since `snapshot`, as compared by
+    // `Component::changed_since`. Ordered as in the snapshot, then as in `export_component`
+    pub fn diff_component<T: Component + Clone + 'static>(
+        &self,
+        snapshot: &[(Entity, T)],
+        epsilon: f32,
+    ) -> Vec<Entity> {
+        let snapshotted: Map<&Entity, ()> =
+            snapshot.iter().map(|(entity, _)| (entity, ())).collect();
+        let mut changed: Vec<Entity> = snapshot
+            .iter()
+            .filter(|(entity, previous)| {
+                self.get_entity_component::<T>(entity)
+                    .is_none_or(|current| current.changed_since(previous, epsilon))
+            })
+            .map(|(entity, _)| entity.clone())
+            .collect();
+        changed.extend(
+            self.alive_entities()
+                .iter()
+                .filter(|entity| self.has_component::<T>(entity))
+                .filter(|entity| !snapshotted.contains_key(entity))
+                .cloned(),
+        );
+        changed
+    }
+
+    // Entities that aren't alive are created with their exported id, ids above
+    // `MAX_ENTITY_ID` are skipped
+    pub fn load_component<T: Component + 'static>(&mut self, components: Vec<(Entity, T)>) {
+        for (entity, component) in components {
+            if !self.is_alive(&entity) && self.create_entity_with_id(entity.0).is_none() {
+                continue;
+            }
+            self.register_component(&entity, component);
+        }
+    }
+
+    // Type names are used as keys since `TypeId`s aren't stable between builds
+    pub fn export_type_registry(&self) -> Vec<(String, ComponentId)> {
+        self.column_type_names()
+            .into_iter()
+            .enumerate()
+            .map(|(id, type_name)| (type_name.to_string(), id))
+            .collect()
+    }
+
+    // Moves registered columns to the ids they had when the registry was exported, so saved
+    // component ids and masks stay valid. Unknown names and out of range ids are skipped, the
+    // remaining columns fill the free ids in their current order
+    pub fn import_type_registry(&mut self, registry: &[(String, ComponentId)]) {
+        let type_names = self.column_type_names();
+        let mut order: Vec<Option<ComponentId>> = vec![None; type_names.len()];
+        let mut placed = vec![false; type_names.len()];
+        for (type_name, id) in registry {
+            let Some(current) = type_names.iter().position(|name| name == type_name) else {
+                continue;
+            };
+            if *id < order.len() && order[*id].is_none() && !placed[current] {
+                order[*id] = Some(current);
+                placed[current] = true;
+            }
+        }
+
+        let mut unplaced = (0..type_names.len()).filter(|&current| !placed[current]);
+        let order: Vec<ComponentId> = order
+            .into_iter()
+            .map(|id| id.or_else(|| unplaced.next()).unwrap())
+            .collect();
+        self.permute_columns(&order);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Mesh, Tag, Transform};
+
+    #[test]
+    fn transform_column_round_trips_on_its_own() {
+        let mut storage = ComponentStorage::new();
+        storage.spawn_batch(3, |i| (Transform { x: i as f32 }, Mesh::default()));
+        storage.remove_entity(Entity(1));
+        let saved = storage.export_component::<Transform>();
+        assert_eq!(saved.len(), 2);
+
+        let mut loaded = ComponentStorage::new();
+        loaded.load_component(saved.clone());
+        assert_eq!(loaded.export_component::<Transform>(), saved);
+        assert!(loaded.get_component_vec::<Mesh>().is_none());
+        assert!(!loaded.is_alive(&Entity(1)));
+        assert_eq!(
+            loaded
+                .get_entity_component::<Transform>(&Entity(2))
+                .unwrap()
+                .x,
+            2.0
+        );
+    }
+
+    #[test]
+    fn imported_registry_remaps_ids_by_name() {
+        let mut saved = ComponentStorage::new();
+        saved.ensure_column::<Transform>();
+        saved.ensure_column::<Mesh>();
+        let registry = saved.export_type_registry();
+
+        // Registered the other way round in this run
+        let mut storage = ComponentStorage::new();
+        let entity = storage.spawn((Mesh::default(), Transform { x: 2.0 }));
+        storage.spawn((Mesh::default(),));
+        assert_eq!(storage.component_mask::<Transform>(), Some(0b10));
+        storage.import_type_registry(&registry);
+        assert_eq!(storage.export_type_registry(), registry);
+        assert_eq!(storage.component_mask::<Transform>(), Some(0b01));
+        assert_eq!(storage.component_mask::<Mesh>(), Some(0b10));
+        assert!(storage.matches(&entity, 0b11, 0));
+        assert!(!storage.matches(&Entity(1), 0b01, 0));
+        assert_eq!(
+            storage
+                .get_entity_component::<Transform>(&entity)
+                .unwrap()
+                .x,
+            2.0
+        );
+        assert_eq!(storage.validate_integrity(), Ok(()));
+    }
+
+    #[test]
+    fn column_moved_below_64_gets_its_mask_bits() {
+        let mut storage = ComponentStorage::new();
+        macro_rules! ensure_columns {
+            ($($n:literal),*) => { $(storage.ensure_column::<Tag<$n>>();)* };
+        }
+        ensure_columns!(
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23,
+            24, 25, 26, 27, 28, 29, 30, 31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45,
+            46, 47, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63, 64, 65, 66, 67,
+            68, 69
+        );
+        let entity = storage.spawn((Transform { x: 1.0 },));
+        assert_eq!(storage.component_mask::<Transform>(), None);
+
+        let registry = [(core::any::type_name::<Transform>().to_string(), 0)];
+        storage.import_type_registry(&registry);
+        assert_eq!(storage.component_mask::<Transform>(), Some(1));
+        assert!(storage.matches(&entity, 1, 0));
+        assert_eq!(storage.validate_integrity(), Ok(()));
+    }
+
+    #[test]
+    fn sub_epsilon_drift_is_not_a_change() {
+        #[derive(Clone, PartialEq)]
+        struct Position(f32);
+
+        impl Component for Position {
+            fn setup(&mut self, _: &ComponentStorage) {}
+            fn update(&mut self, _: &ComponentStorage) {}
+            fn changed_since(&self, other: &Self, epsilon: f32) -> bool {
+                (self.0 - other.0).abs() > epsilon
+            }
+        }
+
+        let mut storage = ComponentStorage::new();
+        let drifting = storage.spawn((Position(1.0),));
+        let moving = storage.spawn((Position(1.0),));
+        let removed = storage.spawn((Position(1.0),));
+        let snapshot = storage.export_component::<Position>();
+        storage
+            .get_entity_component_mut::<Position>(&drifting)
+            .unwrap()
+            .0 += 1e-6;
+        storage
+            .get_entity_component_mut::<Position>(&moving)
+            .unwrap()
+            .0 += 0.5;
+        storage.remove_entity(removed.clone());
+        let added = storage.spawn((Position(0.0),));
+
+        assert_eq!(
+            storage.diff_component(&snapshot, 1e-4),
+            [moving, removed, added]
+        );
+        // Without an epsilon any difference counts
+        assert!(storage.diff_component(&snapshot, 0.0).contains(&drifting));
+    }
+}
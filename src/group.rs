@@ -0,0 +1,114 @@
+use core::any::TypeId;
+
+use crate::component::{Component, ComponentStorage, Entity};
+
+impl ComponentStorage {
+    // A disabled component is skipped by update passes and queries but keeps its data.
+    // Disabling a shared slot skips it for every entity sharing it
+    pub fn set_component_enabled<T: Component + 'static>(
+        &mut self,
+        entity: &Entity,
+        enabled: bool,
+    ) {
+        self.set_component_type_enabled(entity, TypeId::of::<T>(), enabled);
+    }
+
+    pub fn is_component_enabled<T: Component + 'static>(&self, entity: &Entity) -> bool {
+        self.is_component_type_enabled(entity, TypeId::of::<T>())
+    }
+
+    pub fn add_to_group<T: Component + 'static>(&mut self, group: &str) {
+        let types = self.component_groups.entry(group.into()).or_default();
+        if !types.contains(&TypeId::of::<T>()) {
+            types.push(TypeId::of::<T>());
+        }
+    }
+
+    pub fn set_group_enabled(&mut self, entity: &Entity, group: &str, enabled: bool) {
+        let Some(types) = self.component_groups.get(group).cloned() else {
+            return;
+        };
+        for type_id in types {
+            self.set_component_type_enabled(entity, type_id, enabled);
+        }
+    }
+
+    fn set_component_type_enabled(&mut self, entity: &Entity, type_id: TypeId, enabled: bool) {
+        if !self.is_alive(entity) {
+            return;
+        }
+        if enabled {
+            let Some(types) = self.disabled_components.get_mut(entity) else {
+                return;
+            };
+            types.retain(|&disabled| disabled != type_id);
+            if types.is_empty() {
+                self.disabled_components.remove(entity);
+            }
+        } else {
+            let types = self.disabled_components.entry(entity.clone()).or_default();
+            if !types.contains(&type_id) {
+                types.push(type_id);
+            }
+        }
+    }
+
+    pub(crate) fn is_component_type_enabled(&self, entity: &Entity, type_id: TypeId) -> bool {
+        self.disabled_components
+            .get(entity)
+            .is_none_or(|types| !types.contains(&type_id))
+    }
+
+    pub(crate) fn has_enabled_component_type(&self, entity: &Entity, type_id: TypeId) -> bool {
+        self.has_component_type(entity, type_id) && self.is_component_type_enabled(entity, type_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::testing::Counter;
+
+    #[derive(Default, PartialEq)]
+    struct Decay(u32);
+
+    impl Component for Decay {
+        fn setup(&mut self, _: &ComponentStorage) {}
+        fn update(&mut self, _: &ComponentStorage) {
+            self.0 += 1;
+        }
+    }
+
+    #[test]
+    fn disabled_group_skips_both_types_in_updates() {
+        let mut storage = ComponentStorage::new();
+        storage.add_to_group::<Counter>("lod");
+        storage.add_to_group::<Decay>("lod");
+        storage.add_to_group::<Decay>("lod");
+        let far = storage.spawn((Counter::default(), Decay::default()));
+        let near = storage.spawn((Counter::default(), Decay::default()));
+        storage.set_group_enabled(&far, "lod", false);
+        storage.update_components();
+        storage.update_components_mut();
+
+        let counts = |storage: &ComponentStorage, entity| {
+            (
+                storage.get_entity_component::<Counter>(entity).unwrap().n,
+                storage.get_entity_component::<Decay>(entity).unwrap().0,
+            )
+        };
+        assert_eq!(counts(&storage, &far), (0, 0));
+        assert_eq!(counts(&storage, &near), (2, 2));
+        assert_eq!(
+            storage.entities().with::<Decay>().collect(),
+            vec![near.clone()]
+        );
+
+        storage.set_group_enabled(&far, "lod", true);
+        assert!(storage.disabled_components.is_empty());
+        storage.update_components();
+        assert_eq!(counts(&storage, &far), (1, 1));
+    }
+}
@@ -0,0 +1,96 @@
+use core::{cell::Ref, marker::PhantomData};
+
+use crate::component::{Component, ComponentStorage, Entity, WeakEntity};
+
+// Can be kept across frames. Resolving reads the cached slot directly unless a slot has moved
+// since the handle was made, in which case it falls back to the entity's row
+pub struct ComponentHandle<T> {
+    entity: WeakEntity,
+    slot: u32,
+    slot_epoch: u64,
+    marker: PhantomData<T>,
+}
+
+impl<T: Component + 'static> ComponentHandle<T> {
+    pub fn entity(&self) -> &WeakEntity {
+        &self.entity
+    }
+
+    pub fn resolve<'a>(&self, storage: &'a ComponentStorage) -> Option<Ref<'a, T>> {
+        let entity = storage.resolve(&self.entity)?;
+        if storage.slot_epoch() != self.slot_epoch {
+            return storage.get_entity_component::<T>(&entity);
+        }
+        let component = storage.get_component_vec::<T>()?.get(self.slot as usize)?;
+        Some(component.borrow())
+    }
+
+    // Caches the current slot again so later resolves take the direct path
+    pub fn refresh(&mut self, storage: &ComponentStorage) -> bool {
+        let Some(handle) = storage
+            .resolve(&self.entity)
+            .and_then(|entity| storage.component_handle::<T>(&entity))
+        else {
+            return false;
+        };
+        *self = handle;
+        true
+    }
+}
+
+// Derived impls would require `T: Clone`
+impl<T> Clone for ComponentHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            entity: self.entity.clone(),
+            slot: self.slot,
+            slot_epoch: self.slot_epoch,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl ComponentStorage {
+    pub fn component_handle<T: Component + 'static>(
+        &self,
+        entity: &Entity,
+    ) -> Option<ComponentHandle<T>> {
+        Some(ComponentHandle {
+            entity: self.downgrade(entity)?,
+            slot: self.get_entity_component_id::<T>(entity)?,
+            slot_epoch: self.slot_epoch(),
+            marker: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Counter;
+
+    #[test]
+    fn handle_resolves_again_after_a_frame() {
+        let mut storage = ComponentStorage::new();
+        storage.spawn((Counter::default(),));
+        let entity = storage.spawn((Counter::default(),));
+        let mut handle = storage.component_handle::<Counter>(&entity).unwrap();
+        storage.update_components();
+        assert_eq!(handle.resolve(&storage).unwrap().n, 1);
+        assert_eq!(handle.entity(), &storage.downgrade(&entity).unwrap());
+
+        // A moved slot is found through the row until the handle is refreshed
+        storage.sort_component::<Counter, u32>(|counter| u32::MAX - counter.n);
+        storage
+            .get_entity_component_mut::<Counter>(&entity)
+            .unwrap()
+            .n = 9;
+        assert_eq!(handle.resolve(&storage).unwrap().n, 9);
+        assert!(handle.refresh(&storage));
+        assert_eq!(handle.resolve(&storage).unwrap().n, 9);
+
+        storage.remove_entity(entity);
+        assert!(handle.resolve(&storage).is_none());
+        assert!(!handle.refresh(&storage));
+    }
+}
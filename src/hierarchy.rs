@@ -0,0 +1,102 @@
+use core::cell::Ref;
+
+use crate::component::{Component, ComponentStorage, Entity};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Parent(pub Entity);
+
+impl Component for Parent {
+    const HAS_BEHAVIOR: bool = false;
+
+    fn setup(&mut self, _world: &ComponentStorage) {}
+    fn update(&mut self, _world: &ComponentStorage) {}
+
+    fn remap_entities(&mut self, remap: &dyn Fn(Entity) -> Entity) {
+        self.0 = remap(self.0.clone());
+    }
+}
+
+impl ComponentStorage {
+    pub fn parent(&self, entity: &Entity) -> Option<Entity> {
+        let parent = self.get_entity_component::<Parent>(entity)?.0.clone();
+        self.is_alive(&parent).then_some(parent)
+    }
+
+    // Roots, and children whose parent is dead or lacks a `T`, get `None` for the parent side.
+    // Every borrow follows the borrow policy, a pair with a side that can't be borrowed is
+    // skipped as a whole
+    pub fn iter_with_parent<T: Component + 'static>(
+        &self,
+    ) -> impl Iterator<Item = (Entity, Ref<'_, T>, Option<Ref<'_, T>>)> + '_ {
+        self.component_pairs::<T>()
+            .filter_map(move |(entity, component)| {
+                let parent = match self.get_entity_component_cell::<Parent>(&entity) {
+                    Some(parent) => Some(self.borrow_with_policy(parent)?.0.clone()),
+                    None => None,
+                };
+                let parent = parent
+                    .filter(|parent| self.is_alive(parent))
+                    .and_then(|parent| self.get_entity_component_cell::<T>(&parent));
+                let parent = match parent {
+                    Some(parent) => Some(self.borrow_with_policy(parent)?),
+                    None => None,
+                };
+                Some((entity, component, parent))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::{component::BorrowPolicy, testing::Transform};
+
+    #[test]
+    fn child_sees_its_parents_transform() {
+        let mut storage = ComponentStorage::new();
+        let root = storage.spawn((Transform { x: 10.0 },));
+        let child = storage.spawn((Transform { x: 1.0 }, Parent(root.clone())));
+        let orphan = storage.spawn((Transform { x: 3.0 }, Parent(Entity(99))));
+        let pairs: Vec<(Entity, f32, Option<f32>)> = storage
+            .iter_with_parent::<Transform>()
+            .map(|(entity, local, parent)| (entity, local.x, parent.map(|parent| parent.x)))
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                (root.clone(), 10.0, None),
+                (child.clone(), 1.0, Some(10.0)),
+                (orphan, 3.0, None),
+            ]
+        );
+
+        storage.remove_entity(root);
+        assert_eq!(storage.parent(&child), None);
+        let (_, _, parent) = storage.iter_with_parent::<Transform>().next().unwrap();
+        assert!(parent.is_none());
+    }
+
+    #[test]
+    fn borrowed_parent_skips_the_pair_under_skip() {
+        let mut storage = ComponentStorage::new();
+        storage.set_borrow_policy(BorrowPolicy::Skip);
+        let root = storage.spawn((Transform { x: 10.0 },));
+        storage.spawn((Transform { x: 1.0 }, Parent(root.clone())));
+        let other_root = storage.spawn((Transform { x: 20.0 },));
+        let other = storage.spawn((Transform { x: 2.0 }, Parent(other_root.clone())));
+        let held = storage
+            .get_entity_component_mut::<Transform>(&root)
+            .unwrap();
+        let held_parent = storage.get_entity_component_mut::<Parent>(&other).unwrap();
+        let entities: Vec<Entity> = storage
+            .iter_with_parent::<Transform>()
+            .map(|(entity, ..)| entity)
+            .collect();
+        drop((held, held_parent));
+        // `root` itself is borrowed, `child` can't read it and `other` can't read its `Parent`
+        assert_eq!(entities, [other_root]);
+        assert_eq!(storage.iter_with_parent::<Transform>().count(), 4);
+    }
+}
@@ -0,0 +1,232 @@
+use alloc::{vec, vec::Vec};
+use core::any::TypeId;
+
+use crate::{
+    component::{ComponentStorage, Entity},
+    error::IntegrityError,
+    Map,
+};
+
+impl ComponentStorage {
+    // Slots handed out by `reserve_slots` but not spawned yet are the only ones allowed to have
+    // no owner
+    pub fn validate_integrity(&self) -> Result<(), Vec<IntegrityError>> {
+        let mut errors = vec![];
+        let columns: Map<TypeId, usize> = self
+            .component_vectors
+            .iter()
+            .enumerate()
+            .map(|(index, component_vec)| (component_vec.component_type_id(), index))
+            .collect();
+
+        let mut alive = vec![false; self.component_table.len()];
+        for entity in self.alive_entities() {
+            if !self.is_alive(entity) {
+                errors.push(IntegrityError::MissingRow(entity.clone()));
+            } else {
+                alive[entity.0 as usize] = true;
+            }
+        }
+
+        let mut owned: Vec<Vec<bool>> = self
+            .component_vectors
+            .iter()
+            .map(|component_vec| vec![false; component_vec.len()])
+            .collect();
+        for (type_id, slots) in self.reserved_slots.iter() {
+            let Some(&index) = columns.get(type_id) else {
+                continue;
+            };
+            for &slot in slots {
+                if let Some(owned) = owned[index].get_mut(slot as usize) {
+                    *owned = true;
+                }
+            }
+        }
+
+        let mut references: Map<(TypeId, u32), u32> = Map::new();
+        for (id, table) in self.component_table.iter().enumerate() {
+            let Some(table) = table else {
+                continue;
+            };
+            let entity = Entity(id as u32);
+            if !alive[id] {
+                errors.push(IntegrityError::UntrackedRow(entity.clone()));
+            }
+
+            let mut expected = 0;
+            for (&type_id, &component_id) in table.iter() {
+                let Some(&index) = columns.get(&type_id) else {
+                    errors.push(IntegrityError::MissingColumn {
+                        entity: entity.clone(),
+                        type_id,
+                    });
+                    continue;
+                };
+                let component_vec = &self.component_vectors[index];
+                if component_id as usize >= component_vec.len() {
+                    errors.push(IntegrityError::InvalidSlot {
+                        entity: entity.clone(),
+                        type_name: component_vec.type_name(),
+                        component_id,
+                        len: component_vec.len(),
+                    });
+                }
+                if let Some(owned) = owned[index].get_mut(component_id as usize) {
+                    *owned |= alive[id];
+                }
+                expected |= 1u64.checked_shl(index as u32).unwrap_or(0);
+                *references.entry((type_id, component_id)).or_insert(0) += 1;
+            }
+
+            let actual = self.component_masks.get(id).copied().unwrap_or(0);
+            if actual != expected {
+                errors.push(IntegrityError::MaskMismatch {
+                    entity,
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        for (index, owned) in owned.iter().enumerate() {
+            for (component_id, _) in owned.iter().enumerate().filter(|(_, owned)| !**owned) {
+                errors.push(IntegrityError::DanglingSlot {
+                    type_name: self.component_vectors[index].type_name(),
+                    component_id: component_id as u32,
+                });
+            }
+        }
+
+        // Counts are only recorded while a slot has more than one reference
+        let mut slots: Vec<(TypeId, u32)> = references.keys().copied().collect();
+        slots.extend(self.shared_slots.keys().copied());
+        slots.sort();
+        slots.dedup();
+        for slot in slots {
+            let actual = references.get(&slot).copied().unwrap_or(0);
+            let recorded = self
+                .shared_slots
+                .get(&slot)
+                .copied()
+                .unwrap_or(actual.min(1));
+            if recorded != actual {
+                let type_name = columns.get(&slot.0).map_or("<unknown>", |&index| {
+                    self.component_vectors[index].type_name()
+                });
+                errors.push(IntegrityError::ShareCountMismatch {
+                    type_name,
+                    component_id: slot.1,
+                    recorded,
+                    actual,
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+
+    use super::*;
+    use crate::{
+        row::ComponentRow,
+        testing::{Counter, Mesh, Transform},
+    };
+
+    #[test]
+    fn corrupted_storage_reports_each_broken_invariant() {
+        let mut storage = ComponentStorage::new();
+        let a = storage.spawn((Transform::default(),));
+        let b = storage.spawn((Transform::default(), Mesh::default()));
+        let c = storage.create_entity();
+        let d = storage.create_entity();
+        storage.remove_entity(c.clone());
+        assert_eq!(storage.validate_integrity(), Ok(()));
+
+        let mesh_bit = storage.component_mask::<Mesh>().unwrap();
+        let b_mask = storage.component_masks[1];
+        storage.component_table[0]
+            .as_mut()
+            .unwrap()
+            .insert(TypeId::of::<Transform>(), 99);
+        storage.component_table[0]
+            .as_mut()
+            .unwrap()
+            .insert(TypeId::of::<Counter>(), 0);
+        storage.component_masks[1] ^= mesh_bit;
+        storage.component_table[2] = Some(ComponentRow::new());
+        storage.component_table[3] = None;
+        storage.shared_slots.insert((TypeId::of::<Mesh>(), 0), 2);
+
+        let errors = storage.validate_integrity().unwrap_err();
+        let expected = [
+            IntegrityError::MissingRow(d),
+            IntegrityError::UntrackedRow(c),
+            IntegrityError::InvalidSlot {
+                entity: a.clone(),
+                type_name: core::any::type_name::<Transform>(),
+                component_id: 99,
+                len: 2,
+            },
+            IntegrityError::MissingColumn {
+                entity: a,
+                type_id: TypeId::of::<Counter>(),
+            },
+            IntegrityError::MaskMismatch {
+                entity: b,
+                expected: b_mask,
+                actual: b_mask ^ mesh_bit,
+            },
+            IntegrityError::ShareCountMismatch {
+                type_name: core::any::type_name::<Mesh>(),
+                component_id: 0,
+                recorded: 2,
+                actual: 1,
+            },
+            // Left behind by pointing `a` at slot 99
+            IntegrityError::DanglingSlot {
+                type_name: core::any::type_name::<Transform>(),
+                component_id: 0,
+            },
+        ];
+        assert_eq!(errors.len(), expected.len(), "{errors:?}");
+        for error in expected.iter() {
+            assert!(errors.contains(error), "{error:?} missing from {errors:?}");
+        }
+    }
+
+    #[test]
+    fn only_reserved_slots_may_go_unowned() {
+        let mut storage = ComponentStorage::new();
+        storage.spawn((Transform::default(),));
+        storage.reserve_slots::<Transform>(2);
+        storage.spawn_from_slots::<Transform>(1..2);
+        assert_eq!(storage.validate_integrity(), Ok(()));
+
+        storage
+            .get_component_vec_mut::<Transform>()
+            .unwrap()
+            .push(RefCell::new(Transform::default()));
+        let errors = storage.validate_integrity().unwrap_err();
+        let dangling: Vec<u32> = errors
+            .iter()
+            .map(|error| match error {
+                IntegrityError::DanglingSlot {
+                    type_name,
+                    component_id,
+                } if *type_name == core::any::type_name::<Transform>() => *component_id,
+                other => panic!("unexpected {other:?}"),
+            })
+            .collect();
+        // Slot 2 is still reserved, the pushed slot 3 has no owner
+        assert_eq!(dangling, [3]);
+    }
+}
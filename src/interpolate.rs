@@ -0,0 +1,112 @@
+use crate::component::{Component, ComponentStorage};
+
+pub trait Lerp {
+    fn lerp(&self, other: &Self, alpha: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, other: &Self, alpha: f32) -> Self {
+        self + (other - self) * alpha
+    }
+}
+
+impl Lerp for f64 {
+    fn lerp(&self, other: &Self, alpha: f32) -> Self {
+        self + (other - self) * alpha as f64
+    }
+}
+
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct Interpolated<T> {
+    pub prev: T,
+    pub curr: T,
+}
+
+impl<T: Clone> Interpolated<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            prev: value.clone(),
+            curr: value,
+        }
+    }
+}
+
+impl<T: Lerp> Interpolated<T> {
+    pub fn lerp(&self, alpha: f32) -> T {
+        self.prev.lerp(&self.curr, alpha)
+    }
+}
+
+impl<T: Lerp + Clone + PartialEq> Component for Interpolated<T> {
+    const HAS_BEHAVIOR: bool = false;
+
+    fn setup(&mut self, _world: &ComponentStorage) {}
+    fn update(&mut self, _world: &ComponentStorage) {}
+}
+
+impl ComponentStorage {
+    // Call at the start of every fixed step, before `curr` is written for that step. Only
+    // alive entities with the component enabled are stepped
+    pub fn step_interpolated<T: Lerp + Clone + PartialEq + 'static>(&self) {
+        let Some(component_vec) = self.get_component_vec::<Interpolated<T>>() else {
+            return;
+        };
+
+        for (_, component_id) in self.enabled_slots::<Interpolated<T>>() {
+            let Some(component) = component_vec.get(component_id as usize) else {
+                continue;
+            };
+            let Some(mut component) = self.borrow_mut_with_policy(component) else {
+                continue;
+            };
+            let component = &mut *component;
+            component.prev.clone_from(&component.curr);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lerp_after_two_steps_gives_the_midpoint() {
+        let mut storage = ComponentStorage::new();
+        let entity = storage.spawn((Interpolated::new(0.0f32),));
+        for curr in [2.0, 4.0] {
+            storage.step_interpolated::<f32>();
+            storage
+                .get_entity_component_mut::<Interpolated<f32>>(&entity)
+                .unwrap()
+                .curr = curr;
+        }
+        let interpolated = storage
+            .get_entity_component::<Interpolated<f32>>(&entity)
+            .unwrap();
+        assert_eq!(interpolated.prev, 2.0);
+        assert_eq!(interpolated.lerp(0.5), 3.0);
+    }
+
+    #[test]
+    fn disabled_components_are_not_stepped() {
+        let mut storage = ComponentStorage::new();
+        let stepped = storage.spawn((Interpolated::new(0.0f64),));
+        let disabled = storage.spawn((Interpolated::new(0.0f64),));
+        storage.set_component_enabled::<Interpolated<f64>>(&disabled, false);
+        for entity in [&stepped, &disabled] {
+            storage
+                .get_entity_component_mut::<Interpolated<f64>>(entity)
+                .unwrap()
+                .curr = 1.0;
+        }
+        storage.step_interpolated::<f64>();
+        let prev = |entity| {
+            storage
+                .get_entity_component::<Interpolated<f64>>(entity)
+                .unwrap()
+                .prev
+        };
+        assert_eq!(prev(&stepped), 1.0);
+        assert_eq!(prev(&disabled), 0.0);
+    }
+}
@@ -1 +1,85 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+// Without std there is no default hasher, so the ordered map stands in
+#[cfg(feature = "std")]
+pub(crate) type Map<K, V> = std::collections::HashMap<K, V>;
+#[cfg(not(feature = "std"))]
+pub(crate) type Map<K, V> = alloc::collections::BTreeMap<K, V>;
+
+#[cfg(feature = "bench")]
+pub mod bench;
+pub mod bundle;
+pub mod checksum;
+pub mod command;
 pub mod component;
+pub mod cursor;
+pub mod despawn;
+pub mod dyn_component;
+pub mod error;
+pub mod event;
+pub mod export;
+pub mod group;
+pub mod handle;
+pub mod hierarchy;
+pub mod integrity;
+pub mod interpolate;
+pub mod logger;
+pub mod merge;
+pub mod prelude;
+pub mod query;
+pub mod relation;
+mod row;
+pub mod schedule;
+// Cell lookup needs `f32::floor`, which core doesn't provide
+#[cfg(feature = "std")]
+pub mod spatial;
+pub mod sync;
+#[cfg(test)]
+mod testing;
+pub mod trait_query;
+pub mod transaction;
+pub mod version;
+pub mod visit;
+pub mod write_batch;
+
+// Built only without std, where the crate is `no_std` and nothing in here can reach std, so the
+// core API has to keep working on `core` and `alloc` alone
+#[cfg(all(test, not(feature = "std")))]
+mod no_std_tests {
+    use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+    use crate::{
+        component::{Component, ComponentStorage, Entity},
+        Map,
+    };
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Name(String);
+
+    impl Component for Name {
+        fn setup(&mut self, _: &ComponentStorage) {}
+        fn update(&mut self, _: &ComponentStorage) {
+            self.0.push('!');
+        }
+    }
+
+    #[test]
+    fn core_api_runs_on_alloc() {
+        let mut storage = ComponentStorage::new();
+        let entity = storage.spawn((Name("a".into()),));
+        storage.setup_components();
+        storage.update_components();
+        assert_eq!(
+            *storage.get_entity_component::<Name>(&entity).unwrap(),
+            Name("a!".into())
+        );
+        storage.remove_entity(entity.clone());
+        assert!(storage.get_entities().is_empty());
+        assert_eq!(storage.validate_integrity(), Ok(()));
+
+        let map: Map<Entity, Vec<u32>> = BTreeMap::new();
+        assert!(map.is_empty());
+    }
+}
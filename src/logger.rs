@@ -0,0 +1,72 @@
+use alloc::boxed::Box;
+
+pub trait EcsLogger {
+    fn log(&self, message: &str);
+}
+
+// The default logger with std, writes every message to stderr
+#[cfg(feature = "std")]
+pub struct StderrLogger;
+
+#[cfg(feature = "std")]
+impl EcsLogger for StderrLogger {
+    fn log(&self, message: &str) {
+        eprintln!("{message}");
+    }
+}
+
+// The default logger without std, drops every message
+pub struct NullLogger;
+
+impl EcsLogger for NullLogger {
+    fn log(&self, _message: &str) {}
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn default_logger() -> Box<dyn EcsLogger> {
+    Box::new(StderrLogger)
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn default_logger() -> Box<dyn EcsLogger> {
+    Box::new(NullLogger)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{rc::Rc, string::String, vec::Vec};
+    use core::cell::RefCell;
+
+    use super::*;
+    use crate::{
+        component::{BorrowPolicy, ComponentStorage},
+        testing::Counter,
+    };
+
+    struct RecordingLogger(Rc<RefCell<Vec<String>>>);
+
+    impl EcsLogger for RecordingLogger {
+        fn log(&self, message: &str) {
+            self.0.borrow_mut().push(message.into());
+        }
+    }
+
+    #[test]
+    fn borrow_skip_is_logged_through_the_installed_logger() {
+        let mut storage = ComponentStorage::new();
+        let messages = Rc::new(RefCell::new(Vec::new()));
+        storage.set_logger(RecordingLogger(messages.clone()));
+        storage.set_borrow_policy(BorrowPolicy::Log);
+        let entity = storage.spawn((Counter::default(),));
+        {
+            let _held = storage.get_entity_component::<Counter>(&entity).unwrap();
+            storage.update_components();
+        }
+        assert_eq!(messages.borrow().len(), 1);
+        assert!(messages.borrow()[0].contains("Counter"));
+        assert_eq!(
+            storage.get_entity_component::<Counter>(&entity).unwrap().n,
+            0
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{
+    component::{ComponentStorage, Entity, MAX_ENTITY_ID},
+    error::MergeError,
+};
+
+impl ComponentStorage {
+    // Moves every alive entity of `other` to the id `remap` gives it, along with its components,
+    // relations, blobs, disabled components and pending despawns. A `despawn_after` keeps the
+    // frames it had left. `remap` is also handed to `Component::remap_entities` so stored
+    // references follow. Versions and setup state start over, the components count as freshly
+    // inserted into this world. Returns `(old, new)` pairs in ascending old id order. Every
+    // target is checked before anything is moved, on an error neither world has changed
+    pub fn merge(
+        &mut self,
+        mut other: ComponentStorage,
+        remap: impl Fn(Entity) -> Entity,
+    ) -> Result<Vec<(Entity, Entity)>, MergeError> {
+        let mut merged: Vec<(Entity, Entity)> = other
+            .alive_entities()
+            .iter()
+            .map(|entity| (entity.clone(), remap(entity.clone())))
+            .collect();
+        merged.sort();
+        let mut targets: Vec<&Entity> = merged.iter().map(|(_, target)| target).collect();
+        targets.sort();
+        for (index, &target) in targets.iter().enumerate() {
+            if target.0 > MAX_ENTITY_ID {
+                return Err(MergeError::TargetAboveCap(target.clone()));
+            }
+            if self.is_alive(target) {
+                return Err(MergeError::TargetAlive(target.clone()));
+            }
+            if index > 0 && targets[index - 1] == target {
+                return Err(MergeError::DuplicateTarget(target.clone()));
+            }
+        }
+        for (_, target) in merged.iter() {
+            self.create_entity_with_id(target.0);
+        }
+
+        let mut component_vectors = core::mem::take(&mut other.component_vectors);
+        for component_vec in component_vectors.iter_mut() {
+            let type_id = component_vec.component_type_id();
+            let mut owners = vec![vec![]; component_vec.len()];
+            for (source, target) in merged.iter() {
+                let slot = other.component_table[source.0 as usize]
+                    .as_ref()
+                    .and_then(|table| table.get(&type_id));
+                if let Some(&slot) = slot {
+                    owners[slot as usize].push(target.clone());
+                }
+            }
+            component_vec.merge_into(self, &owners, &remap);
+        }
+
+        for ((relation, from), related) in core::mem::take(&mut other.relations) {
+            for to in related {
+                self.relate(relation, &remap(from.clone()), &remap(to));
+            }
+        }
+        self.merge_dyn_components(core::mem::take(&mut other.dyn_components), &remap);
+
+        for (source, target) in merged.iter() {
+            if let Some(types) = other.disabled_components.remove(source) {
+                self.disabled_components.insert(target.clone(), types);
+            }
+        }
+        let frame = self.current_frame();
+        for (entity, due) in core::mem::take(&mut other.despawn_timers) {
+            let left = due.saturating_sub(other.current_frame());
+            self.despawn_timers.push((remap(entity), frame + left));
+        }
+        let queued = core::mem::take(&mut *other.despawn_queue.borrow_mut());
+        self.despawn_queue
+            .borrow_mut()
+            .extend(queued.into_iter().map(&remap));
+        Ok(merged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hierarchy::Parent, testing::Transform};
+
+    fn sub_world() -> ComponentStorage {
+        let mut sub = ComponentStorage::new();
+        let root = sub.spawn((Transform { x: 1.0 },));
+        sub.spawn((Transform { x: 2.0 }, Parent(root)));
+        sub
+    }
+
+    #[test]
+    fn merged_sub_world_keeps_its_parent_links() {
+        let mut world = ComponentStorage::new();
+        world.spawn_batch(3, |i| (Transform { x: 10.0 + i as f32 },));
+        let merged = world
+            .merge(sub_world(), |entity| Entity(entity.0 + 100))
+            .unwrap();
+        assert_eq!(merged, [(Entity(0), Entity(100)), (Entity(1), Entity(101))]);
+        assert_eq!(world.alive_count(), 5);
+        assert_eq!(world.parent(&Entity(101)), Some(Entity(100)));
+        assert_eq!(
+            world
+                .get_entity_component::<Transform>(&Entity(101))
+                .unwrap()
+                .x,
+            2.0
+        );
+        assert_eq!(
+            world
+                .get_entity_component::<Transform>(&Entity(2))
+                .unwrap()
+                .x,
+            12.0
+        );
+        assert_eq!(world.validate_integrity(), Ok(()));
+    }
+
+    #[test]
+    fn bad_targets_are_reported_before_anything_moves() {
+        let mut world = ComponentStorage::new();
+        world.spawn_batch(3, |_| (Transform::default(),));
+        let before = world.get_entities();
+        assert_eq!(
+            world.merge(sub_world(), |entity| Entity(entity.0 + 2)),
+            Err(MergeError::TargetAlive(Entity(2)))
+        );
+        assert_eq!(
+            world.merge(sub_world(), |_| Entity(50)),
+            Err(MergeError::DuplicateTarget(Entity(50)))
+        );
+        assert_eq!(
+            world.merge(sub_world(), |entity| Entity(entity.0 + MAX_ENTITY_ID)),
+            Err(MergeError::TargetAboveCap(Entity(MAX_ENTITY_ID + 1)))
+        );
+        assert_eq!(world.get_entities(), before);
+        assert_eq!(world.next_entity_id(), 3);
+        assert!(!world.is_registered::<Parent>());
+    }
+
+    #[test]
+    fn disabled_components_and_pending_despawns_come_along() {
+        let mut sub = sub_world();
+        sub.run(3, |_| {});
+        sub.set_component_enabled::<Transform>(&Entity(0), false);
+        sub.despawn_after(&Entity(1), 2);
+        let mut world = ComponentStorage::new();
+        world.spawn((Transform::default(),));
+        let inserted = world.next_change_tick();
+
+        world.merge(sub, |entity| Entity(entity.0 + 10)).unwrap();
+        assert!(!world.is_component_enabled::<Transform>(&Entity(10)));
+        assert!(world.is_component_enabled::<Transform>(&Entity(11)));
+        assert!(world.component_version::<Transform>(&Entity(11)).unwrap() >= inserted);
+
+        world.run(1, |_| {});
+        assert!(world.is_alive(&Entity(11)));
+        world.run(1, |_| {});
+        assert!(!world.is_alive(&Entity(11)));
+        assert!(world.is_alive(&Entity(10)));
+        assert_eq!(world.validate_integrity(), Ok(()));
+    }
+}
@@ -0,0 +1,26 @@
+//! Everything a typical world needs in one import
+//!
+//! ```
+//! use probable_spork_ecs::prelude::*;
+//!
+//! #[derive(PartialEq, Default)]
+//! struct Position(f32);
+//!
+//! impl Component for Position {
+//!     fn setup(&mut self, _: &ComponentStorage) {}
+//!     fn update(&mut self, _: &ComponentStorage) {
+//!         self.0 += 1.0;
+//!     }
+//! }
+//!
+//! let mut world = ComponentStorage::new();
+//! world.set_borrow_policy(BorrowPolicy::Skip);
+//! let entity: Entity = world.spawn((Position(0.0),));
+//! let weak: WeakEntity = world.downgrade(&entity).unwrap();
+//! world.run(2, |_| {});
+//! assert_eq!(world.get_entity_component::<Position>(&entity).unwrap().0, 2.0);
+//! assert_eq!(world.resolve(&weak), Some(entity));
+//! ```
+
+pub use crate::bundle::Bundle;
+pub use crate::component::{BorrowPolicy, Component, ComponentStorage, Entity, WeakEntity};
@@ -0,0 +1,701 @@
+//! Query iterators borrow the storage, so the borrow checker rejects structural changes while
+//! one is live
+//!
+//! ```compile_fail,E0502
+//! use probable_spork_ecs::prelude::*;
+//!
+//! #[derive(PartialEq)]
+//! struct Position(f32);
+//!
+//! impl Component for Position {
+//!     fn setup(&mut self, _: &ComponentStorage) {}
+//!     fn update(&mut self, _: &ComponentStorage) {}
+//! }
+//!
+//! let mut world = ComponentStorage::new();
+//! world.spawn((Position(0.0),));
+//! for (entity, _) in world.component_pairs::<Position>() {
+//!     world.remove_entity(entity);
+//! }
+//! ```
+
+use alloc::{vec, vec::Vec};
+use core::{
+    any::TypeId,
+    cell::{Ref, RefCell, RefMut},
+};
+
+use crate::component::{BorrowPolicy, Component, ComponentStorage, Entity};
+
+// Implemented for tuples of `Clone` components, fetches owned copies for a single entity
+pub trait CloneQuery {
+    type Item;
+
+    fn fetch(storage: &ComponentStorage, entity: &Entity) -> Option<Self::Item>;
+}
+
+macro_rules! impl_clone_query {
+    ($($component:ident),+) => {
+        impl<$($component: Component + Clone + 'static),+> CloneQuery for ($($component,)+) {
+            type Item = (Entity, $($component),+);
+
+            fn fetch(storage: &ComponentStorage, entity: &Entity) -> Option<Self::Item> {
+                Some((
+                    entity.clone(),
+                    $({
+                        let component = storage.get_entity_component_cell::<$component>(entity)?;
+                        storage.borrow_with_policy(component)?.clone()
+                    }),+
+                ))
+            }
+        }
+    };
+}
+
+impl_clone_query!(A);
+impl_clone_query!(A, B);
+impl_clone_query!(A, B, C);
+impl_clone_query!(A, B, C, D);
+
+impl ComponentStorage {
+    pub fn collect_query<Q: CloneQuery>(&self) -> Vec<Q::Item> {
+        self.alive_entities()
+            .iter()
+            .filter_map(|entity| Q::fetch(self, entity))
+            .collect()
+    }
+
+    // Slots of `T` held by alive entities that have it enabled, in alive order. Slots nobody
+    // holds are never reached, a shared slot comes up once per entity holding it
+    pub(crate) fn enabled_slots<T: Component + 'static>(
+        &self,
+    ) -> impl Iterator<Item = (&Entity, u32)> + '_ {
+        self.alive_entities().iter().filter_map(move |entity| {
+            if !self.is_component_enabled::<T>(entity) {
+                return None;
+            }
+            Some((entity, self.get_entity_component_id::<T>(entity)?))
+        })
+    }
+
+    // Lazily borrows each component in turn under the borrow policy, nothing is collected up
+    // front
+    pub fn component_pairs<T: Component + 'static>(
+        &self,
+    ) -> impl Iterator<Item = (Entity, Ref<'_, T>)> + '_ {
+        let component_vec = self.get_component_vec::<T>();
+        self.enabled_slots::<T>()
+            .filter_map(move |(entity, component_id)| {
+                let component = component_vec?.get(component_id as usize)?;
+                Some((entity.clone(), self.borrow_with_policy(component)?))
+            })
+    }
+
+    // Same items as `component_pairs`, but the matching slots are found up front so the
+    // iterator knows how many remain
+    pub fn iter_component<T: Component + 'static>(&self) -> ComponentIter<'_, T> {
+        let slots = self
+            .enabled_slots::<T>()
+            .map(|(entity, component_id)| (entity.clone(), component_id))
+            .collect::<Vec<_>>();
+        ComponentIter {
+            storage: self,
+            component_vec: self.get_component_vec::<T>().map_or(&[], Vec::as_slice),
+            slots: slots.into_iter(),
+        }
+    }
+
+    // Walks the owners of whichever column is shorter and resolves the other type through their
+    // rows, so items come in that column's slot order. Both borrows follow the borrow policy.
+    // Entities sharing their `A` slot are skipped
+    pub fn join_mut<A: Component + 'static, B: Component + 'static>(
+        &self,
+    ) -> impl Iterator<Item = (Entity, RefMut<'_, A>, Ref<'_, B>)> + '_ {
+        assert_ne!(
+            TypeId::of::<A>(),
+            TypeId::of::<B>(),
+            "join_mut needs two distinct component types"
+        );
+        let a_vec = self.get_component_vec::<A>();
+        let b_vec = self.get_component_vec::<B>();
+        let owners = match (a_vec, b_vec) {
+            (Some(a), Some(b)) if a.len() <= b.len() => self.column_owners::<A>(),
+            (Some(_), Some(_)) => self.column_owners::<B>(),
+            _ => vec![],
+        };
+        owners.into_iter().filter_map(move |entity| {
+            if !self.is_component_enabled::<A>(&entity) || !self.is_component_enabled::<B>(&entity)
+            {
+                return None;
+            }
+            let a_id = self.get_entity_component_id::<A>(&entity)?;
+            if self.is_shared_slot::<A>(a_id) {
+                return None;
+            }
+            let b_id = self.get_entity_component_id::<B>(&entity)?;
+            let a = self.borrow_mut_with_policy(a_vec?.get(a_id as usize)?)?;
+            let b = self.borrow_with_policy(b_vec?.get(b_id as usize)?)?;
+            self.bump_version(&entity, TypeId::of::<A>());
+            Some((entity, a, b))
+        })
+    }
+
+    // Tags are marker components, usually with `HAS_BEHAVIOR` set to false. A disabled tag
+    // counts as missing
+    pub fn query_tagged<T: Component + 'static, Tag: Component + 'static>(
+        &self,
+    ) -> impl Iterator<Item = (Entity, Ref<'_, T>)> + '_ {
+        let tag_type = TypeId::of::<Tag>();
+        self.component_pairs::<T>()
+            .filter(move |(entity, _)| self.has_enabled_component_type(entity, tag_type))
+    }
+
+    // Entities that are dead, lack `T` or share their `T` slot are skipped instead of being an
+    // error. Borrows follow the borrow policy
+    pub fn for_each_present<T: Component + 'static>(
+        &self,
+        entities: &[Entity],
+        mut f: impl FnMut(&Entity, RefMut<T>),
+    ) {
+        for entity in entities {
+            let Some(cell) = self.get_entity_component_cell_mut::<T>(entity) else {
+                continue;
+            };
+            if let Some(component) = self.borrow_mut_with_policy(cell) {
+                self.bump_version(entity, TypeId::of::<T>());
+                f(entity, component);
+            }
+        }
+    }
+}
+
+pub struct ComponentIter<'a, T> {
+    storage: &'a ComponentStorage,
+    component_vec: &'a [RefCell<T>],
+    slots: vec::IntoIter<(Entity, u32)>,
+}
+
+impl<'a, T: 'static> Iterator for ComponentIter<'a, T> {
+    type Item = (Entity, Ref<'a, T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (entity, component_id) in self.slots.by_ref() {
+            let component = self.component_vec.get(component_id as usize);
+            if let Some(component) = component.and_then(|c| self.storage.borrow_with_policy(c)) {
+                return Some((entity, component));
+            }
+        }
+        None
+    }
+
+    // Under `Skip` or `Log` a component that can't be borrowed is passed over, so only `Panic`
+    // promises every remaining slot is yielded
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.remaining();
+        match self.storage.borrow_policy() {
+            BorrowPolicy::Panic => (remaining, Some(remaining)),
+            BorrowPolicy::Skip | BorrowPolicy::Log => (0, Some(remaining)),
+        }
+    }
+}
+
+impl<T> ComponentIter<'_, T> {
+    // Slots not reached yet, an upper bound on the items left for progress reporting
+    pub fn remaining(&self) -> usize {
+        self.slots.len()
+    }
+}
+
+pub struct EntityQueryBuilder<'a> {
+    storage: &'a ComponentStorage,
+    with: Vec<TypeId>,
+    without: Vec<TypeId>,
+}
+
+impl EntityQueryBuilder<'_> {
+    pub fn with<T: Component + 'static>(mut self) -> Self {
+        self.with.push(TypeId::of::<T>());
+        self
+    }
+
+    pub fn without<T: Component + 'static>(mut self) -> Self {
+        self.without.push(TypeId::of::<T>());
+        self
+    }
+
+    // Disabled components count as missing
+    fn is_match(&self, entity: &Entity) -> bool {
+        self.with
+            .iter()
+            .all(|&type_id| self.storage.has_enabled_component_type(entity, type_id))
+            && !self
+                .without
+                .iter()
+                .any(|&type_id| self.storage.has_enabled_component_type(entity, type_id))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.storage
+            .alive_entities()
+            .iter()
+            .filter(|entity| self.is_match(entity))
+            .cloned()
+    }
+
+    pub fn collect(&self) -> Vec<Entity> {
+        self.iter().collect()
+    }
+
+    // Matches in ascending id order, whatever order the entities were created in
+    pub fn page(&self, offset: usize, limit: usize) -> Vec<Entity> {
+        (0..self.storage.next_entity_id())
+            .map(Entity)
+            .filter(|entity| self.storage.is_alive(entity) && self.is_match(entity))
+            .skip(offset)
+            .take(limit)
+            .collect()
+    }
+}
+
+impl ComponentStorage {
+    pub fn entities(&self) -> EntityQueryBuilder<'_> {
+        EntityQueryBuilder {
+            storage: self,
+            with: vec![],
+            without: vec![],
+        }
+    }
+
+    // `entities()` for callers that only know the component types at runtime
+    pub fn query_dynamic(&self, required: &[TypeId], forbidden: &[TypeId]) -> Vec<Entity> {
+        EntityQueryBuilder {
+            storage: self,
+            with: required.to_vec(),
+            without: forbidden.to_vec(),
+        }
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{Counter, Mesh, Transform, Velocity},
+        visit::ComponentVisitor,
+    };
+
+    struct CountingVisitor(usize);
+
+    impl ComponentVisitor for CountingVisitor {
+        fn visit(&mut self, _: Entity, _: &str, _: &dyn core::any::Any) {
+            self.0 += 1;
+        }
+    }
+
+    // Every iteration API has to come up empty without indexing past a missing row or column
+    fn assert_iterates_nothing(storage: &mut ComponentStorage) {
+        storage.setup_components();
+        storage.update_components();
+        storage.update_components_mut();
+        storage.update_components_with(&mut 0u32);
+        storage.run(2, |_| {});
+        assert!(storage.get_entities().is_empty());
+        assert_eq!(storage.alive_count(), 0);
+        assert!(!storage.matches(&Entity(0), 0, 0));
+        assert_eq!(storage.component_pairs::<Transform>().count(), 0);
+        assert_eq!(storage.iter_component::<Transform>().remaining(), 0);
+        assert_eq!(storage.join_mut::<Transform, Mesh>().count(), 0);
+        assert_eq!(storage.query_tagged::<Transform, Mesh>().count(), 0);
+        assert!(storage.collect_query::<(Transform, Mesh)>().is_empty());
+        storage.for_each_present::<Transform>(&[Entity(0)], |_, _| unreachable!());
+        assert!(storage.entities().with::<Transform>().collect().is_empty());
+        assert!(storage.entities().page(0, 10).is_empty());
+        assert!(storage
+            .query_dynamic(&[TypeId::of::<Transform>()], &[])
+            .is_empty());
+        assert!(storage.query_trait::<dyn core::fmt::Debug>().is_empty());
+        assert_eq!(storage.iter_with_parent::<Transform>().count(), 0);
+        assert!(storage.cursor::<Transform>().next().is_none());
+        assert!(storage.get_many::<Transform>(&[Entity(0)])[0].is_none());
+        assert!(storage.export_component::<Transform>().is_empty());
+        let mut visitor = CountingVisitor(0);
+        storage.visit_all(&mut visitor);
+        assert_eq!(visitor.0, 0);
+        storage.step_interpolated::<f32>();
+        storage.sort_component::<Transform, i32>(|transform| transform.x as i32);
+        assert!(storage
+            .chunks_mut::<Transform>(3)
+            .iter()
+            .all(|chunk| chunk.is_empty()));
+        storage.despawn_batch(&[Entity(0)]);
+        storage.remove_entity(Entity(0));
+        storage.shrink_to_fit();
+        assert_eq!(storage.validate_integrity(), Ok(()));
+    }
+
+    #[test]
+    fn new_world_iterates_nothing() {
+        let mut storage = ComponentStorage::new();
+        assert_iterates_nothing(&mut storage);
+        assert!(storage.get_component_vec::<Transform>().is_none());
+        assert!(storage.component_mask::<Transform>().is_none());
+        assert!(storage.sync_get_mut::<Transform>(&Entity(0)).is_none());
+        assert!(storage.free_slots().is_empty());
+    }
+
+    #[test]
+    fn world_with_empty_columns_iterates_nothing() {
+        let mut storage = ComponentStorage::new();
+        storage.ensure_column::<Transform>();
+        storage.add_component_vec::<Mesh>(vec![]);
+        let entity = storage.spawn((Counter::default(),));
+        storage.remove_entity(entity);
+        assert_iterates_nothing(&mut storage);
+        assert_eq!(storage.free_slots(), vec![0]);
+    }
+
+    #[test]
+    fn collected_pairs_are_owned_and_sortable() {
+        let mut storage = ComponentStorage::new();
+        storage.spawn((Transform { x: 2.0 }, Mesh { label: "b".into() }));
+        storage.spawn((Transform { x: 9.0 },));
+        storage.spawn((Transform { x: 1.0 }, Mesh { label: "a".into() }));
+
+        let mut pairs = storage.collect_query::<(Transform, Mesh)>();
+        pairs.sort_by(|a, b| a.1.x.total_cmp(&b.1.x));
+        // Nothing is borrowed any more, the world can be written right away
+        storage
+            .get_entity_component_mut::<Mesh>(&Entity(0))
+            .unwrap()
+            .label = "c".into();
+
+        let labels: Vec<&str> = pairs
+            .iter()
+            .map(|(_, _, mesh)| mesh.label.as_str())
+            .collect();
+        assert_eq!(labels, vec!["a", "b"]);
+        assert_eq!(pairs[0].0, Entity(2));
+    }
+
+    #[test]
+    fn builder_combines_with_and_without() {
+        let mut storage = ComponentStorage::new();
+        let plain = storage.spawn((Transform::default(),));
+        storage.spawn((Transform::default(), Mesh::default()));
+        storage.spawn((Mesh::default(),));
+        let other = storage.spawn((Transform::default(), Counter::default()));
+        assert_eq!(
+            storage
+                .entities()
+                .with::<Transform>()
+                .without::<Mesh>()
+                .collect(),
+            vec![plain, other]
+        );
+        assert_eq!(storage.entities().collect().len(), 4);
+    }
+
+    // Counts allocations per thread, so tests running in parallel don't see each other's
+    #[cfg(feature = "std")]
+    mod counting {
+        extern crate std;
+
+        use core::cell::Cell;
+        use std::alloc::{GlobalAlloc, Layout, System};
+
+        std::thread_local! {
+            static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+        }
+
+        struct CountingAllocator;
+
+        unsafe impl GlobalAlloc for CountingAllocator {
+            unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+                let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+                unsafe { System.alloc(layout) }
+            }
+
+            unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+                unsafe { System.dealloc(ptr, layout) }
+            }
+        }
+
+        #[global_allocator]
+        static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+        pub(super) fn allocations_in(f: impl FnOnce()) -> usize {
+            let before = ALLOCATIONS.with(Cell::get);
+            f();
+            ALLOCATIONS.with(Cell::get) - before
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn component_pairs_yields_every_pair_without_allocating() {
+        let mut storage = ComponentStorage::new();
+        storage.spawn_batch(5, |i| (Transform { x: i as f32 },));
+        storage.create_entity();
+        // Warms up anything recorded lazily on first access
+        assert_eq!(storage.component_pairs::<Transform>().count(), 5);
+
+        let mut total = 0.0;
+        let allocations = counting::allocations_in(|| {
+            for (_, transform) in storage.component_pairs::<Transform>() {
+                total += transform.x;
+            }
+        });
+        assert_eq!(allocations, 0);
+        assert_eq!(total, 10.0);
+        assert_eq!(storage.component_pairs::<Mesh>().count(), 0);
+    }
+
+    #[test]
+    fn component_pairs_skips_a_borrowed_component_under_skip() {
+        let mut storage = ComponentStorage::new();
+        storage.set_borrow_policy(BorrowPolicy::Skip);
+        let entities = storage.spawn_batch(3, |i| (Transform { x: i as f32 },));
+        let _held = storage
+            .get_entity_component_mut::<Transform>(&entities[1])
+            .unwrap();
+        let seen: Vec<Entity> = storage
+            .component_pairs::<Transform>()
+            .map(|(entity, _)| entity)
+            .collect();
+        assert_eq!(seen, [entities[0].clone(), entities[2].clone()]);
+    }
+
+    #[test]
+    fn only_enemy_transforms_are_yielded() {
+        #[derive(PartialEq)]
+        struct Enemy;
+
+        impl Component for Enemy {
+            const HAS_BEHAVIOR: bool = false;
+            fn setup(&mut self, _: &ComponentStorage) {}
+            fn update(&mut self, _: &ComponentStorage) {}
+        }
+
+        let mut storage = ComponentStorage::new();
+        let first = storage.spawn((Transform { x: 1.0 }, Enemy));
+        storage.spawn((Transform { x: 2.0 },));
+        let second = storage.spawn((Transform { x: 3.0 }, Enemy));
+        storage.spawn((Enemy,));
+        let disabled = storage.spawn((Transform { x: 4.0 }, Enemy));
+        storage.set_component_enabled::<Enemy>(&disabled, false);
+        let tagged: Vec<(Entity, f32)> = storage
+            .query_tagged::<Transform, Enemy>()
+            .map(|(entity, transform)| (entity, transform.x))
+            .collect();
+        assert_eq!(tagged, [(first, 1.0), (second, 3.0)]);
+    }
+
+    fn integrate(storage: &ComponentStorage) -> Vec<Entity> {
+        storage
+            .join_mut::<Transform, Velocity>()
+            .map(|(entity, mut transform, velocity)| {
+                transform.x += velocity.dx;
+                entity
+            })
+            .collect()
+    }
+
+    #[test]
+    fn join_integrates_position_by_velocity() {
+        let mut storage = ComponentStorage::new();
+        let moving = storage.spawn_batch(3, |i| {
+            (Transform { x: i as f32 }, Velocity { dx: 1.0 + i as f32 })
+        });
+        // More transforms than velocities, so the velocity column drives the join
+        let still = storage.spawn((Transform { x: 7.0 },));
+        storage.spawn((Transform::default(),));
+        storage.spawn((Velocity { dx: 5.0 },));
+        storage.set_component_enabled::<Velocity>(&moving[2], false);
+
+        assert_eq!(integrate(&storage), moving[..2]);
+        integrate(&storage);
+        let xs: Vec<f32> = [&moving[0], &moving[1], &moving[2], &still]
+            .map(|entity| storage.get_entity_component::<Transform>(entity).unwrap().x)
+            .to_vec();
+        assert_eq!(xs, [2.0, 5.0, 2.0, 7.0]);
+
+        // Off the dense path the owners come from the rows, in slot order
+        storage.sort_component::<Velocity, i32>(|velocity| -velocity.dx as i32);
+        storage.set_component_enabled::<Velocity>(&moving[2], true);
+        assert_eq!(
+            integrate(&storage),
+            [moving[2].clone(), moving[1].clone(), moving[0].clone()]
+        );
+    }
+
+    #[test]
+    fn join_skips_borrowed_and_shared_components() {
+        let mut storage = ComponentStorage::new();
+        storage.set_borrow_policy(BorrowPolicy::Skip);
+        let entities = storage.spawn_batch(3, |_| (Transform::default(), Velocity { dx: 1.0 }));
+        let sharer = storage.spawn((Velocity { dx: 1.0 },));
+        storage.share_component::<Transform>(&entities[0], &sharer);
+        {
+            let _held = storage
+                .get_entity_component::<Transform>(&entities[1])
+                .unwrap();
+            assert_eq!(integrate(&storage), [entities[2].clone()]);
+        }
+        assert_eq!(
+            storage
+                .get_entity_component::<Transform>(&entities[2])
+                .unwrap()
+                .x,
+            1.0
+        );
+    }
+
+    // The only structural change reachable through `&self` is a dropped `DespawnGuard`, and that
+    // only queues the entity until `flush_despawns`
+    #[test]
+    fn despawn_queued_mid_iteration_waits_for_the_flush() {
+        let mut storage = ComponentStorage::new();
+        let (_, first) = storage.spawn_scoped((Transform { x: 1.0 },));
+        let (_, second) = storage.spawn_scoped((Transform { x: 2.0 },));
+        let mut guards = vec![first, second];
+        let mut seen = vec![];
+        for (entity, transform) in storage.component_pairs::<Transform>() {
+            guards.pop();
+            seen.push((entity, transform.x));
+        }
+        assert_eq!(seen, [(Entity(0), 1.0), (Entity(1), 2.0)]);
+        assert_eq!(storage.alive_count(), 2);
+        storage.flush_despawns();
+        assert_eq!(storage.alive_count(), 0);
+    }
+
+    #[test]
+    fn second_page_holds_the_next_50_ids() {
+        let mut storage = ComponentStorage::new();
+        // Created back to front, pages still come in id order
+        for id in (0..130).rev() {
+            let entity = storage.create_entity_with_id(id).unwrap();
+            if id % 13 == 0 {
+                storage.register_component(&entity, Mesh::default());
+            } else {
+                storage.register_component(&entity, Transform::default());
+            }
+        }
+        let query = storage.entities().with::<Transform>();
+        let matching: Vec<u32> = (0..130).filter(|id| id % 13 != 0).collect();
+        assert_eq!(matching.len(), 120);
+        let page: Vec<u32> = query.page(50, 50).iter().map(Entity::index).collect();
+        assert_eq!(page, matching[50..100]);
+        assert_eq!(query.page(100, 50).len(), 20);
+        assert!(query.page(120, 50).is_empty());
+    }
+
+    #[test]
+    fn remaining_counts_down_as_the_iterator_advances() {
+        let mut storage = ComponentStorage::new();
+        let entities = storage.spawn_batch(4, |i| (Transform { x: i as f32 },));
+        storage.spawn((Mesh::default(),));
+        storage.set_component_enabled::<Transform>(&entities[3], false);
+        let mut iter = storage.iter_component::<Transform>();
+        assert_eq!(iter.size_hint(), (3, Some(3)));
+        for remaining in (0..3).rev() {
+            assert!(iter.next().is_some());
+            assert_eq!(iter.remaining(), remaining);
+            assert_eq!(iter.size_hint(), (remaining, Some(remaining)));
+        }
+        assert!(iter.next().is_none());
+        assert_eq!(iter.remaining(), 0);
+    }
+
+    #[test]
+    fn iter_component_passes_over_a_borrowed_component_under_skip() {
+        let mut storage = ComponentStorage::new();
+        storage.set_borrow_policy(BorrowPolicy::Skip);
+        let entities = storage.spawn_batch(3, |i| (Transform { x: i as f32 },));
+        let _held = storage
+            .get_entity_component_mut::<Transform>(&entities[0])
+            .unwrap();
+        let mut iter = storage.iter_component::<Transform>();
+        assert_eq!(iter.size_hint(), (0, Some(3)));
+        assert_eq!(
+            iter.next().map(|(entity, _)| entity),
+            Some(entities[1].clone())
+        );
+        assert_eq!(iter.remaining(), 1);
+    }
+
+    #[test]
+    fn size_hint_bounds_the_count_under_skip() {
+        let mut storage = ComponentStorage::new();
+        storage.set_borrow_policy(BorrowPolicy::Skip);
+        let entities = storage.spawn_batch(4, |i| (Transform { x: i as f32 },));
+        let _held = storage
+            .get_entity_component_mut::<Transform>(&entities[2])
+            .unwrap();
+        let iter = storage.iter_component::<Transform>();
+        let (lower, upper) = iter.size_hint();
+        let count = iter.count();
+        assert_eq!(count, 3);
+        assert!(lower <= count && upper.unwrap() >= count);
+    }
+
+    #[test]
+    fn dynamic_query_matches_the_typed_one() {
+        let mut storage = ComponentStorage::new();
+        storage.spawn((Transform::default(), Mesh::default()));
+        storage.spawn((Transform::default(),));
+        storage.spawn((Transform::default(), Mesh::default(), Counter::default()));
+        storage.spawn((Mesh::default(),));
+        let required = [TypeId::of::<Transform>(), TypeId::of::<Mesh>()];
+        let typed: Vec<Entity> = storage
+            .collect_query::<(Transform, Mesh)>()
+            .into_iter()
+            .map(|(entity, _, _)| entity)
+            .collect();
+        assert_eq!(storage.query_dynamic(&required, &[]), typed);
+        assert_eq!(typed, [Entity(0), Entity(2)]);
+        assert_eq!(
+            storage.query_dynamic(&required, &[TypeId::of::<Counter>()]),
+            [Entity(0)]
+        );
+    }
+
+    #[test]
+    fn for_each_present_skips_dead_and_missing_entities() {
+        let mut storage = ComponentStorage::new();
+        let valid = storage.spawn((Transform { x: 1.0 },));
+        let missing = storage.spawn((Mesh::default(),));
+        let dead = storage.spawn((Transform { x: 2.0 },));
+        let other = storage.spawn((Transform { x: 3.0 },));
+        storage.remove_entity(dead.clone());
+
+        let mut visited = vec![];
+        storage.for_each_present::<Transform>(
+            &[valid.clone(), missing, dead, Entity(99), other.clone()],
+            |entity, mut transform| {
+                transform.x += 10.0;
+                visited.push(entity.clone());
+            },
+        );
+        assert_eq!(visited, [valid.clone(), other]);
+        assert_eq!(
+            storage.get_entity_component::<Transform>(&valid).unwrap().x,
+            11.0
+        );
+    }
+
+    #[test]
+    fn for_each_present_passes_over_a_borrowed_component_under_skip() {
+        let mut storage = ComponentStorage::new();
+        storage.set_borrow_policy(BorrowPolicy::Skip);
+        let entities = storage.spawn_batch(3, |i| (Transform { x: i as f32 },));
+        let held = storage
+            .get_entity_component::<Transform>(&entities[1])
+            .unwrap();
+        let mut visited = vec![];
+        storage.for_each_present::<Transform>(&entities, |entity, _| visited.push(entity.clone()));
+        drop(held);
+        assert_eq!(visited, [entities[0].clone(), entities[2].clone()]);
+    }
+}
@@ -0,0 +1,73 @@
+use crate::component::{ComponentStorage, Entity};
+use alloc::vec::Vec;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RelationId(pub u32);
+
+impl ComponentStorage {
+    pub fn relate(&mut self, relation: RelationId, from: &Entity, to: &Entity) {
+        if !self.is_alive(from) || !self.is_alive(to) {
+            return;
+        }
+        let related = self.relations.entry((relation, from.clone())).or_default();
+        if !related.contains(to) {
+            related.push(to.clone());
+        }
+    }
+
+    pub fn unrelate(&mut self, relation: RelationId, from: &Entity, to: &Entity) {
+        let key = (relation, from.clone());
+        let Some(related) = self.relations.get_mut(&key) else {
+            return;
+        };
+        related.retain(|entity| entity != to);
+        if related.is_empty() {
+            self.relations.remove(&key);
+        }
+    }
+
+    pub fn related(&self, relation: RelationId, from: &Entity) -> &[Entity] {
+        self.relations
+            .get(&(relation, from.clone()))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    // Drops every relation the entity takes part in, on either end
+    pub(crate) fn remove_relations(&mut self, entity: &Entity) {
+        self.relations.retain(|(_, from), related| {
+            related.retain(|to| to != entity);
+            from != entity && !related.is_empty()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OWNS: RelationId = RelationId(0);
+
+    #[test]
+    fn despawned_item_drops_out_of_the_owner() {
+        let mut storage = ComponentStorage::new();
+        let owner = storage.create_entity();
+        let items: Vec<Entity> = (0..3).map(|_| storage.create_entity()).collect();
+        for item in &items {
+            storage.relate(OWNS, &owner, item);
+        }
+        storage.relate(OWNS, &owner, &items[0]);
+        assert_eq!(storage.related(OWNS, &owner), &items[..]);
+
+        storage.remove_entity(items[1].clone());
+        assert_eq!(
+            storage.related(OWNS, &owner),
+            &[items[0].clone(), items[2].clone()]
+        );
+        storage.relate(OWNS, &owner, &items[1]);
+        assert_eq!(storage.related(OWNS, &owner).len(), 2);
+
+        storage.remove_entity(owner.clone());
+        assert!(storage.related(OWNS, &owner).is_empty());
+        assert!(storage.relations.is_empty());
+    }
+}
@@ -0,0 +1,119 @@
+use alloc::{boxed::Box, vec::Vec};
+use core::any::TypeId;
+
+// One entity's slot per component type, kept sorted by `TypeId`. Entities hold a handful of
+// components, so an exactly sized slice searched in place is both smaller and quicker to walk
+// than a map per entity. Adding and removing a type reallocates, lookups never do. Only the
+// map operations the storage uses are provided
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct ComponentRow(Box<[(TypeId, u32)]>);
+
+impl ComponentRow {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    fn position(&self, type_id: &TypeId) -> Result<usize, usize> {
+        self.0.binary_search_by(|(id, _)| id.cmp(type_id))
+    }
+
+    pub(crate) fn get(&self, type_id: &TypeId) -> Option<&u32> {
+        let index = self.position(type_id).ok()?;
+        Some(&self.0[index].1)
+    }
+
+    pub(crate) fn get_mut(&mut self, type_id: &TypeId) -> Option<&mut u32> {
+        let index = self.position(type_id).ok()?;
+        Some(&mut self.0[index].1)
+    }
+
+    pub(crate) fn contains_key(&self, type_id: &TypeId) -> bool {
+        self.position(type_id).is_ok()
+    }
+
+    // Returns the slot it replaced, like `Map::insert`
+    pub(crate) fn insert(&mut self, type_id: TypeId, component_id: u32) -> Option<u32> {
+        match self.position(&type_id) {
+            Ok(index) => Some(core::mem::replace(&mut self.0[index].1, component_id)),
+            Err(index) => {
+                let mut entries = Vec::from(core::mem::take(&mut self.0));
+                entries.insert(index, (type_id, component_id));
+                self.0 = entries.into_boxed_slice();
+                None
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, type_id: &TypeId) -> Option<u32> {
+        let index = self.position(type_id).ok()?;
+        let mut entries = Vec::from(core::mem::take(&mut self.0));
+        let (_, component_id) = entries.remove(index);
+        self.0 = entries.into_boxed_slice();
+        Some(component_id)
+    }
+
+    pub(crate) fn iter(&self) -> <&Self as IntoIterator>::IntoIter {
+        self.into_iter()
+    }
+
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &TypeId> + '_ {
+        self.0.iter().map(|(type_id, _)| type_id)
+    }
+
+    // Heap bytes held by the row
+    #[cfg(all(test, feature = "bench"))]
+    pub(crate) fn heap_size(&self) -> usize {
+        core::mem::size_of_val(&*self.0)
+    }
+}
+
+impl<'a> IntoIterator for &'a ComponentRow {
+    type Item = (&'a TypeId, &'a u32);
+    type IntoIter = core::iter::Map<
+        core::slice::Iter<'a, (TypeId, u32)>,
+        fn(&'a (TypeId, u32)) -> (&'a TypeId, &'a u32),
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0
+            .iter()
+            .map(|(type_id, component_id)| (type_id, component_id))
+    }
+}
+
+impl IntoIterator for ComponentRow {
+    type Item = (TypeId, u32);
+    type IntoIter = alloc::vec::IntoIter<(TypeId, u32)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Vec::from(self.0).into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn row_behaves_like_a_map() {
+        let (a, b, c) = (TypeId::of::<u8>(), TypeId::of::<u16>(), TypeId::of::<u32>());
+        let mut row = ComponentRow::new();
+        assert_eq!(row.insert(b, 1), None);
+        assert_eq!(row.insert(a, 2), None);
+        assert_eq!(row.insert(c, 3), None);
+        assert_eq!(row.insert(a, 4), Some(2));
+        *row.get_mut(&c).unwrap() = 5;
+        assert_eq!(
+            (row.get(&a), row.get(&b), row.get(&c)),
+            (Some(&4), Some(&1), Some(&5))
+        );
+
+        let mut sorted = [a, b, c];
+        sorted.sort();
+        assert!(row.keys().copied().eq(sorted));
+        assert_eq!(row.remove(&b), Some(1));
+        assert_eq!(row.remove(&b), None);
+        assert!(!row.contains_key(&b) && row.contains_key(&a));
+        assert_eq!(row.into_iter().count(), 2);
+    }
+}
@@ -0,0 +1,203 @@
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::fmt;
+
+use crate::{command::CommandBuffer, component::ComponentStorage};
+
+type System = Box<dyn FnMut(&mut ComponentStorage)>;
+type SetSystem = Box<dyn FnMut(&mut ComponentStorage, &mut CommandBuffer)>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScheduleError {
+    UnknownLabel(String),
+    // Labels of the systems that could not be ordered
+    Cycle(Vec<String>),
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduleError::UnknownLabel(label) => write!(f, "no system labeled {label}"),
+            ScheduleError::Cycle(labels) => {
+                write!(f, "ordering cycle between {}", labels.join(", "))
+            }
+        }
+    }
+}
+
+impl core::error::Error for ScheduleError {}
+
+#[derive(Default)]
+pub struct Schedule {
+    systems: Vec<(String, System)>,
+    orderings: Vec<(String, String)>,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Adding a label that already exists replaces its system
+    pub fn add_system_labeled(
+        &mut self,
+        name: impl Into<String>,
+        system: impl FnMut(&mut ComponentStorage) + 'static,
+    ) {
+        let name = name.into();
+        match self.systems.iter_mut().find(|(label, _)| *label == name) {
+            Some((_, existing)) => *existing = Box::new(system),
+            None => self.systems.push((name, Box::new(system))),
+        }
+    }
+
+    pub fn order(&mut self, before: impl Into<String>, after: impl Into<String>) {
+        self.orderings.push((before.into(), after.into()));
+    }
+
+    fn system_index(&self, label: &str) -> Result<usize, ScheduleError> {
+        self.systems
+            .iter()
+            .position(|(name, _)| name == label)
+            .ok_or_else(|| ScheduleError::UnknownLabel(label.to_string()))
+    }
+
+    // Unconstrained systems keep the order they were added in
+    fn sorted_indices(&self) -> Result<Vec<usize>, ScheduleError> {
+        let mut dependencies = vec![vec![]; self.systems.len()];
+        for (before, after) in self.orderings.iter() {
+            dependencies[self.system_index(after)?].push(self.system_index(before)?);
+        }
+
+        let mut sorted = Vec::with_capacity(self.systems.len());
+        let mut placed = vec![false; self.systems.len()];
+        while sorted.len() < self.systems.len() {
+            let next = (0..self.systems.len()).find(|&index| {
+                !placed[index] && dependencies[index].iter().all(|&before| placed[before])
+            });
+            let Some(next) = next else {
+                let remaining = (0..self.systems.len())
+                    .filter(|&index| !placed[index])
+                    .map(|index| self.systems[index].0.clone())
+                    .collect();
+                return Err(ScheduleError::Cycle(remaining));
+            };
+            placed[next] = true;
+            sorted.push(next);
+        }
+        Ok(sorted)
+    }
+
+    pub fn sorted_labels(&self) -> Result<Vec<String>, ScheduleError> {
+        let sorted = self.sorted_indices()?;
+        Ok(sorted
+            .into_iter()
+            .map(|index| self.systems[index].0.clone())
+            .collect())
+    }
+
+    pub fn run(&mut self, storage: &mut ComponentStorage) -> Result<(), ScheduleError> {
+        for index in self.sorted_indices()? {
+            (self.systems[index].1)(storage);
+        }
+        Ok(())
+    }
+}
+
+// Systems that share one command buffer, flushed once after every member has run so later
+// members never see a half applied set of changes
+#[derive(Default)]
+pub struct SystemSet {
+    systems: Vec<SetSystem>,
+    commands: CommandBuffer,
+}
+
+impl SystemSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_system(
+        &mut self,
+        system: impl FnMut(&mut ComponentStorage, &mut CommandBuffer) + 'static,
+    ) {
+        self.systems.push(Box::new(system));
+    }
+
+    pub fn run(&mut self, storage: &mut ComponentStorage) {
+        for system in self.systems.iter_mut() {
+            system(storage, &mut self.commands);
+        }
+        self.commands.apply(storage);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+    use core::cell::RefCell;
+
+    use super::*;
+    use crate::testing::Transform;
+
+    #[test]
+    fn constraints_sort_three_systems_and_cycles_error() {
+        let mut schedule = Schedule::new();
+        let ran = Rc::new(RefCell::new(Vec::new()));
+        for label in ["render", "physics", "input"] {
+            let ran = ran.clone();
+            schedule.add_system_labeled(label, move |_| ran.borrow_mut().push(label));
+        }
+        schedule.order("input", "physics");
+        schedule.order("physics", "render");
+        assert_eq!(
+            schedule.sorted_labels().unwrap(),
+            ["input", "physics", "render"]
+        );
+        let mut storage = ComponentStorage::new();
+        schedule.run(&mut storage).unwrap();
+        assert_eq!(*ran.borrow(), ["input", "physics", "render"]);
+
+        schedule.order("render", "input");
+        assert_eq!(
+            schedule.run(&mut storage),
+            Err(ScheduleError::Cycle(vec![
+                "render".to_string(),
+                "physics".to_string(),
+                "input".to_string()
+            ]))
+        );
+        schedule.order("missing", "input");
+        assert_eq!(
+            schedule.sorted_labels(),
+            Err(ScheduleError::UnknownLabel("missing".to_string()))
+        );
+        assert_eq!(ran.borrow().len(), 3);
+    }
+
+    #[test]
+    fn set_spawns_land_only_after_every_member_ran() {
+        let mut set = SystemSet::new();
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        for label in [1.0, 2.0] {
+            let seen = seen.clone();
+            set.add_system(move |storage, commands| {
+                seen.borrow_mut().push(storage.alive_count());
+                commands.spawn((Transform { x: label },));
+            });
+        }
+        let mut storage = ComponentStorage::new();
+        set.run(&mut storage);
+        assert_eq!(*seen.borrow(), [0, 0]);
+        assert_eq!(storage.alive_count(), 2);
+        let xs: Vec<f32> = storage
+            .component_pairs::<Transform>()
+            .map(|(_, transform)| transform.x)
+            .collect();
+        assert_eq!(xs, [1.0, 2.0]);
+    }
+}
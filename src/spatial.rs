@@ -0,0 +1,90 @@
+use crate::Map;
+use alloc::vec::Vec;
+
+use crate::component::{Component, ComponentStorage, Entity};
+
+pub trait Position {
+    fn position(&self) -> (f32, f32);
+}
+
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: Map<(i32, i32), Vec<Entity>>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: Map::new(),
+        }
+    }
+
+    pub fn cell_of(&self, (x, y): (f32, f32)) -> (i32, i32) {
+        (
+            (x / self.cell_size).floor() as i32,
+            (y / self.cell_size).floor() as i32,
+        )
+    }
+
+    pub fn insert<P: Position>(&mut self, entity: Entity, position: &P) {
+        let cell = self.cell_of(position.position());
+        self.cells.entry(cell).or_default().push(entity);
+    }
+
+    pub fn query_cell(&self, cx: i32, cy: i32) -> &[Entity] {
+        self.cells.get(&(cx, cy)).map_or(&[], Vec::as_slice)
+    }
+
+    // Entities in the given cell and the eight cells around it
+    pub fn query_neighbors(&self, cx: i32, cy: i32) -> Vec<Entity> {
+        (cy - 1..=cy + 1)
+            .flat_map(|y| (cx - 1..=cx + 1).map(move |x| (x, y)))
+            .flat_map(|(x, y)| self.query_cell(x, y).iter().cloned())
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    // Re-buckets every alive entity holding `T`, call after positions have moved
+    pub fn rebuild<T: Component + Position + 'static>(&mut self, storage: &ComponentStorage) {
+        self.clear();
+        for entity in storage.get_entities() {
+            if let Some(component) = storage.get_entity_component::<T>(&entity) {
+                let cell = self.cell_of(component.position());
+                self.cells.entry(cell).or_default().push(entity.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Transform;
+
+    impl Position for Transform {
+        fn position(&self) -> (f32, f32) {
+            (self.x, 0.0)
+        }
+    }
+
+    #[test]
+    fn entities_land_in_their_cells_and_neighbors() {
+        let mut storage = ComponentStorage::new();
+        let entities = storage.spawn_batch(4, |i| (Transform { x: i as f32 * 10.0 },));
+        let mut hash = SpatialHash::new(10.0);
+        hash.rebuild::<Transform>(&storage);
+        assert_eq!(hash.query_cell(1, 0), &[entities[1].clone()]);
+        assert_eq!(hash.query_neighbors(1, 0), entities[..3].to_vec());
+        assert!(hash.query_cell(0, 1).is_empty());
+
+        hash.insert(Entity(99), &Transform { x: -5.0 });
+        assert_eq!(hash.query_cell(-1, 0), &[Entity(99)]);
+        storage.remove_entity(entities[0].clone());
+        hash.rebuild::<Transform>(&storage);
+        assert!(hash.query_cell(-1, 0).is_empty() && hash.query_cell(0, 0).is_empty());
+    }
+}
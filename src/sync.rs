@@ -0,0 +1,115 @@
+use core::{
+    any::TypeId,
+    cell::RefCell,
+    ops::{Deref, DerefMut},
+};
+
+use crate::component::{Component, ComponentStorage, Entity};
+
+// Works on a local copy of the component and commits it back into storage when dropped. The
+// commit borrows under the storage's `BorrowPolicy`, under `Skip` or `Log` a write that finds
+// the component borrowed is dropped
+pub struct SyncGuard<'a, T: Component + Clone + 'static> {
+    storage: &'a ComponentStorage,
+    entity: Entity,
+    cell: &'a RefCell<T>,
+    value: T,
+}
+
+impl<T: Component + Clone + 'static> Deref for SyncGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Component + Clone + 'static> DerefMut for SyncGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T: Component + Clone + 'static> Drop for SyncGuard<'_, T> {
+    fn drop(&mut self) {
+        if let Some(mut component) = self.storage.borrow_mut_with_policy(self.cell) {
+            component.clone_from(&self.value);
+            self.storage.bump_version(&self.entity, TypeId::of::<T>());
+        }
+    }
+}
+
+impl ComponentStorage {
+    // `None` for a shared slot, like `get_entity_component_mut`
+    pub fn sync_get_mut<T: Component + Clone + 'static>(
+        &self,
+        entity: &Entity,
+    ) -> Option<SyncGuard<'_, T>> {
+        let cell = self.get_entity_component_cell_mut::<T>(entity)?;
+        let value = self.borrow_with_policy(cell)?.clone();
+        Some(SyncGuard {
+            storage: self,
+            entity: entity.clone(),
+            cell,
+            value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{component::BorrowPolicy, testing::Transform};
+
+    #[test]
+    fn storage_reflects_the_guard_once_dropped() {
+        let mut storage = ComponentStorage::new();
+        let entity = storage.spawn((Transform { x: 1.0 },));
+        let before = storage.component_version::<Transform>(&entity).unwrap();
+        {
+            let mut guard = storage.sync_get_mut::<Transform>(&entity).unwrap();
+            guard.x = 5.0;
+            assert_eq!(
+                storage
+                    .get_entity_component::<Transform>(&entity)
+                    .unwrap()
+                    .x,
+                1.0
+            );
+        }
+        assert_eq!(
+            storage
+                .get_entity_component::<Transform>(&entity)
+                .unwrap()
+                .x,
+            5.0
+        );
+        assert!(storage.component_version::<Transform>(&entity).unwrap() > before);
+        assert!(storage.sync_get_mut::<Transform>(&Entity(7)).is_none());
+    }
+
+    #[test]
+    fn dropping_while_borrowed_follows_the_borrow_policy() {
+        let mut storage = ComponentStorage::new();
+        storage.set_borrow_policy(BorrowPolicy::Skip);
+        let entity = storage.spawn((Transform { x: 1.0 },));
+        let before = storage.component_version::<Transform>(&entity).unwrap();
+        {
+            let mut guard = storage.sync_get_mut::<Transform>(&entity).unwrap();
+            guard.x = 5.0;
+            let _held = storage.get_entity_component::<Transform>(&entity).unwrap();
+            drop(guard);
+        }
+        assert_eq!(
+            storage
+                .get_entity_component::<Transform>(&entity)
+                .unwrap()
+                .x,
+            1.0
+        );
+        assert_eq!(
+            storage.component_version::<Transform>(&entity),
+            Some(before)
+        );
+    }
+}
@@ -0,0 +1,90 @@
+// Components shared by the unit tests of several modules, some are only used with optional
+// features enabled
+#![allow(dead_code)]
+
+use alloc::string::String;
+
+use crate::component::{Component, ComponentStorage};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Transform {
+    pub(crate) x: f32,
+}
+
+impl Component for Transform {
+    fn setup(&mut self, _: &ComponentStorage) {}
+    fn update(&mut self, _: &ComponentStorage) {}
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Mesh {
+    pub(crate) label: String,
+}
+
+impl Component for Mesh {
+    fn setup(&mut self, _: &ComponentStorage) {}
+    fn update(&mut self, _: &ComponentStorage) {}
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Velocity {
+    pub(crate) dx: f32,
+}
+
+impl Component for Velocity {
+    const HAS_BEHAVIOR: bool = false;
+    fn setup(&mut self, _: &ComponentStorage) {}
+    fn update(&mut self, _: &ComponentStorage) {}
+}
+
+// Counts its updates
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Counter {
+    pub(crate) n: u32,
+}
+
+impl Component for Counter {
+    fn setup(&mut self, _: &ComponentStorage) {}
+    fn update(&mut self, _: &ComponentStorage) {
+        self.n += 1;
+    }
+}
+
+// Counts its setups
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct SetupCount {
+    pub(crate) n: u32,
+}
+
+impl Component for SetupCount {
+    fn setup(&mut self, _: &ComponentStorage) {
+        self.n += 1;
+    }
+    fn update(&mut self, _: &ComponentStorage) {}
+}
+
+// A data-only marker, distinct `N`s give as many distinct columns as a test needs
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Tag<const N: usize>;
+
+impl<const N: usize> Component for Tag<N> {
+    const HAS_BEHAVIOR: bool = false;
+    fn setup(&mut self, _: &ComponentStorage) {}
+    fn update(&mut self, _: &ComponentStorage) {}
+}
+
+// xorshift64, enough to drive the fuzz-style tests deterministically
+pub(crate) struct Rng(pub(crate) u64);
+
+impl Rng {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub(crate) fn below(&mut self, bound: u32) -> u32 {
+        (self.next() % bound as u64) as u32
+    }
+}
@@ -0,0 +1,122 @@
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::{any::TypeId, cell::Ref};
+
+use crate::component::{Component, ComponentStorage};
+
+type CollectFn<Tr> = Box<dyn for<'a> Fn(&'a ComponentStorage, &mut Vec<Ref<'a, Tr>>)>;
+
+pub(crate) struct TraitCaster<Tr: ?Sized> {
+    component_type: TypeId,
+    collect: CollectFn<Tr>,
+}
+
+impl ComponentStorage {
+    // `cast` is usually just `|component| component`, letting the compiler unsize to `Tr`
+    pub fn register_trait<T: Component + 'static, Tr: ?Sized + 'static>(
+        &mut self,
+        cast: fn(&T) -> &Tr,
+    ) {
+        let casters = self
+            .trait_casters
+            .entry(TypeId::of::<Tr>())
+            .or_insert_with(|| Box::new(Vec::<TraitCaster<Tr>>::new()))
+            .downcast_mut::<Vec<TraitCaster<Tr>>>()
+            .expect("trait caster registry holds casters for a different trait");
+
+        let component_type = TypeId::of::<T>();
+        if casters
+            .iter()
+            .any(|caster| caster.component_type == component_type)
+        {
+            return;
+        }
+
+        casters.push(TraitCaster {
+            component_type,
+            collect: Box::new(move |storage, components| {
+                if let Some(component_vec) = storage.get_component_vec::<T>() {
+                    components.extend(storage.enabled_slots::<T>().filter_map(
+                        |(_, component_id)| {
+                            let component = component_vec.get(component_id as usize)?;
+                            let component = storage.borrow_with_policy(component)?;
+                            Some(Ref::map(component, cast))
+                        },
+                    ));
+                }
+            }),
+        });
+    }
+
+    // Registered columns in registration order, each like `component_pairs`: alive entities
+    // with the component enabled, one item per entity holding a shared slot
+    pub fn query_trait<Tr: ?Sized + 'static>(&self) -> Vec<Ref<'_, Tr>> {
+        let mut components = vec![];
+        let Some(casters) = self
+            .trait_casters
+            .get(&TypeId::of::<Tr>())
+            .and_then(|casters| casters.downcast_ref::<Vec<TraitCaster<Tr>>>())
+        else {
+            return components;
+        };
+
+        for caster in casters {
+            (caster.collect)(self, &mut components);
+        }
+        components
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{format, string::String};
+
+    use super::*;
+    use crate::testing::{Mesh, Transform};
+
+    trait Drawable {
+        fn name(&self) -> String;
+    }
+
+    impl Drawable for Transform {
+        fn name(&self) -> String {
+            format!("t{}", self.x)
+        }
+    }
+
+    impl Drawable for Mesh {
+        fn name(&self) -> String {
+            self.label.clone()
+        }
+    }
+
+    fn names(storage: &ComponentStorage) -> Vec<String> {
+        storage
+            .query_trait::<dyn Drawable>()
+            .iter()
+            .map(|drawable| drawable.name())
+            .collect()
+    }
+
+    #[test]
+    fn two_drawable_types_iterate_uniformly() {
+        let mut storage = ComponentStorage::new();
+        storage.spawn((Transform { x: 1.0 }, Mesh { label: "m".into() }));
+        storage.spawn((Transform { x: 2.0 },));
+        storage.register_trait::<Transform, dyn Drawable>(|component| component);
+        storage.register_trait::<Mesh, dyn Drawable>(|component| component);
+        storage.register_trait::<Mesh, dyn Drawable>(|component| component);
+        assert_eq!(names(&storage), vec!["t1", "t2", "m"]);
+    }
+
+    #[test]
+    fn despawned_and_disabled_components_are_left_out() {
+        let mut storage = ComponentStorage::new();
+        storage.register_trait::<Transform, dyn Drawable>(|component| component);
+        let a = storage.spawn((Transform { x: 1.0 },));
+        let b = storage.spawn((Transform { x: 2.0 },));
+        storage.spawn((Transform { x: 3.0 },));
+        storage.remove_entity(a);
+        storage.set_component_enabled::<Transform>(&b, false);
+        assert_eq!(names(&storage), vec!["t3"]);
+    }
+}
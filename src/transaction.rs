@@ -0,0 +1,239 @@
+use alloc::{boxed::Box, vec::Vec};
+use core::{any::TypeId, cell::RefCell};
+
+use crate::{
+    component::{Component, ComponentArray, ComponentStorage, Entity},
+    dyn_component::DynComponents,
+    error::TransactionError,
+    relation::RelationId,
+    row::ComponentRow,
+    Map,
+};
+
+pub(crate) type SnapshotColumnFn = fn(&dyn ComponentArray) -> Box<dyn ComponentArray>;
+
+pub(crate) fn snapshot_column<T: Component + Clone + 'static>(
+    component_vec: &dyn ComponentArray,
+) -> Box<dyn ComponentArray> {
+    let copy: Vec<RefCell<T>> = component_vec
+        .as_any()
+        .downcast_ref::<Vec<RefCell<T>>>()
+        .into_iter()
+        .flatten()
+        .map(|component| RefCell::new(component.borrow().clone()))
+        .collect();
+    Box::new(copy)
+}
+
+// Everything `rollback` puts back
+pub(crate) struct Checkpoint {
+    component_vectors: Vec<Box<dyn ComponentArray>>,
+    column_order: Vec<usize>,
+    column_setup: Vec<Vec<bool>>,
+    column_dense: Vec<bool>,
+    component_table: Vec<Option<ComponentRow>>,
+    component_masks: Vec<u64>,
+    generations: Vec<u32>,
+    entities: u32,
+    alive_entities: Vec<Entity>,
+    shared_slots: Map<(TypeId, u32), u32>,
+    reserved_slots: Map<TypeId, Vec<u32>>,
+    relations: Map<(RelationId, Entity), Vec<Entity>>,
+    despawn_timers: Vec<(Entity, u64)>,
+    dyn_components: DynComponents,
+    disabled_components: Map<Entity, Vec<TypeId>>,
+    component_versions: Map<(Entity, TypeId), u32>,
+    change_tick: u32,
+}
+
+impl ComponentStorage {
+    // Copies every column along with the entities, their rows, sharing, relations, blobs,
+    // disabled components and pending `despawn_after`s, so every column's type has to be
+    // registered through `register_cloneable`. Columns are copied whole, the cost grows with
+    // the world rather than with the changes. Side effects of hooks such as `on_despawn` and
+    // the frame counter aren't part of it
+    pub fn begin_transaction(&mut self) -> Result<(), TransactionError> {
+        if self.transaction.is_some() {
+            return Err(TransactionError::AlreadyOpen);
+        }
+        let mut component_vectors = Vec::with_capacity(self.component_vectors.len());
+        for component_vec in self.component_vectors.iter() {
+            let type_id = component_vec.component_type_id();
+            let Some(snapshot) = self.snapshot_fns.get(&type_id) else {
+                return Err(TransactionError::NotCloneable(component_vec.type_name()));
+            };
+            component_vectors.push(snapshot(component_vec.as_ref()));
+        }
+        self.transaction = Some(Box::new(Checkpoint {
+            component_vectors,
+            column_order: self.column_order.clone(),
+            column_setup: self
+                .column_setup
+                .iter()
+                .map(|set_up| set_up.borrow().clone())
+                .collect(),
+            column_dense: self.column_dense.clone(),
+            component_table: self.component_table.clone(),
+            component_masks: self.component_masks.clone(),
+            generations: self.generations.clone(),
+            entities: self.entities,
+            alive_entities: self.alive_entities.clone(),
+            shared_slots: self.shared_slots.clone(),
+            reserved_slots: self.reserved_slots.clone(),
+            relations: self.relations.clone(),
+            despawn_timers: self.despawn_timers.clone(),
+            dyn_components: self.dyn_components.clone(),
+            disabled_components: self.disabled_components.clone(),
+            component_versions: self.component_versions.borrow().clone(),
+            change_tick: self.change_tick.get(),
+        }));
+        Ok(())
+    }
+
+    pub fn in_transaction(&self) -> bool {
+        self.transaction.is_some()
+    }
+
+    // Keeps every change, does nothing without an open transaction
+    pub fn commit(&mut self) {
+        self.transaction = None;
+    }
+
+    // Puts the storage back as it was at `begin_transaction`, does nothing without an open
+    // transaction. Components changed in between get a new version rather than their old one,
+    // so a reader that saw the change also sees it undone
+    pub fn rollback(&mut self) {
+        let Some(checkpoint) = self.transaction.take() else {
+            return;
+        };
+        let checkpoint = *checkpoint;
+        let changed: Vec<(Entity, TypeId)> = self
+            .component_versions
+            .get_mut()
+            .iter()
+            .filter(|(_, &version)| version > checkpoint.change_tick)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        self.component_vectors = checkpoint.component_vectors;
+        self.column_order = checkpoint.column_order;
+        self.column_setup = checkpoint
+            .column_setup
+            .into_iter()
+            .map(RefCell::new)
+            .collect();
+        self.column_dense = checkpoint.column_dense;
+        self.component_table = checkpoint.component_table;
+        self.component_masks = checkpoint.component_masks;
+        self.generations = checkpoint.generations;
+        self.entities = checkpoint.entities;
+        self.alive_entities = checkpoint.alive_entities;
+        self.shared_slots = checkpoint.shared_slots;
+        self.reserved_slots = checkpoint.reserved_slots;
+        self.relations = checkpoint.relations;
+        self.despawn_timers = checkpoint.despawn_timers;
+        self.dyn_components = checkpoint.dyn_components;
+        self.disabled_components = checkpoint.disabled_components;
+        *self.component_versions.get_mut() = checkpoint.component_versions;
+        // Slots may have moved back, and the cached column index may point elsewhere
+        self.slot_epoch += 1;
+        self.last_accessed_column.set(0);
+
+        for (entity, type_id) in changed {
+            if self.has_component_type(&entity, type_id) {
+                self.bump_version(&entity, type_id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Mesh, Transform, Velocity};
+
+    fn world() -> (ComponentStorage, Entity) {
+        let mut storage = ComponentStorage::new();
+        storage.register_cloneable::<Transform>();
+        storage.register_cloneable::<Mesh>();
+        let entity = storage.spawn((Transform { x: 1.0 }, Mesh::default()));
+        (storage, entity)
+    }
+
+    #[test]
+    fn rollback_restores_a_mutated_transform() {
+        let (mut storage, entity) = world();
+        storage.begin_transaction().unwrap();
+        let version = storage.component_version::<Transform>(&entity).unwrap();
+        storage
+            .get_entity_component_mut::<Transform>(&entity)
+            .unwrap()
+            .x = 5.0;
+        let changed = storage.component_version::<Transform>(&entity).unwrap();
+        storage.rollback();
+        assert!(!storage.in_transaction());
+        assert_eq!(
+            storage
+                .get_entity_component::<Transform>(&entity)
+                .unwrap()
+                .x,
+            1.0
+        );
+        assert!(storage.component_version::<Transform>(&entity).unwrap() > changed);
+        assert!(changed > version);
+    }
+
+    #[test]
+    fn rollback_undoes_structural_changes() {
+        let (mut storage, entity) = world();
+        let other = storage.spawn((Transform { x: 2.0 },));
+        let weak = storage.downgrade(&other).unwrap();
+        storage.begin_transaction().unwrap();
+
+        storage.remove_component::<Mesh>(&entity);
+        storage.remove_entity(other.clone());
+        let spawned = storage.spawn((Transform { x: 3.0 }, Velocity::default()));
+        storage.set_component_enabled::<Transform>(&entity, false);
+        storage.rollback();
+
+        assert_eq!(storage.get_entities(), [entity.clone(), other.clone()]);
+        assert!(storage.has_component::<Mesh>(&entity));
+        assert!(storage.is_component_enabled::<Transform>(&entity));
+        assert_eq!(storage.resolve(&weak), Some(other));
+        assert!(!storage.is_alive(&spawned));
+        assert!(!storage.is_registered::<Velocity>());
+        assert_eq!(storage.validate_integrity(), Ok(()));
+    }
+
+    #[test]
+    fn commit_keeps_changes_and_only_cloneable_worlds_can_begin() {
+        let (mut storage, entity) = world();
+        storage.begin_transaction().unwrap();
+        assert_eq!(
+            storage.begin_transaction(),
+            Err(TransactionError::AlreadyOpen)
+        );
+        storage
+            .get_entity_component_mut::<Transform>(&entity)
+            .unwrap()
+            .x = 5.0;
+        storage.commit();
+        storage.rollback();
+        assert_eq!(
+            storage
+                .get_entity_component::<Transform>(&entity)
+                .unwrap()
+                .x,
+            5.0
+        );
+
+        storage.spawn((Velocity::default(),));
+        assert_eq!(
+            storage.begin_transaction(),
+            Err(TransactionError::NotCloneable(core::any::type_name::<
+                Velocity,
+            >()))
+        );
+        assert!(!storage.in_transaction());
+    }
+}
@@ -0,0 +1,123 @@
+use core::any::TypeId;
+
+use crate::component::{Component, ComponentStorage, Entity};
+
+impl ComponentStorage {
+    // Taken from one counter shared by every component, so versions of different components
+    // can be compared with each other. Set when the component is inserted and bumped by every
+    // accessor that hands it out mutably, from `get_entity_component_mut` and `join_mut` to
+    // `take_component` and `raw_component_ptr_mut`. `chunks_mut` and `split_columns_mut` bump
+    // every owner of the columns they lend. The update passes and the raw column accessors
+    // `get_component_vec` and `get_component_vec_mut` leave it alone
+    pub fn component_version<T: Component + 'static>(&self, entity: &Entity) -> Option<u32> {
+        if !self.has_component::<T>(entity) {
+            return None;
+        }
+        self.component_versions
+            .borrow()
+            .get(&(entity.clone(), TypeId::of::<T>()))
+            .copied()
+    }
+
+    // The version the next change will get
+    pub fn next_change_tick(&self) -> u32 {
+        self.change_tick.get() + 1
+    }
+
+    pub(crate) fn bump_version(&self, entity: &Entity, type_id: TypeId) {
+        let tick = self.change_tick.get() + 1;
+        self.change_tick.set(tick);
+        self.component_versions
+            .borrow_mut()
+            .insert((entity.clone(), type_id), tick);
+    }
+
+    pub(crate) fn bump_column_versions<T: Component + 'static>(&self) {
+        for entity in self.column_owners::<T>() {
+            self.bump_version(&entity, TypeId::of::<T>());
+        }
+    }
+
+    pub(crate) fn forget_version(&mut self, entity: &Entity, type_id: TypeId) {
+        self.component_versions
+            .get_mut()
+            .remove(&(entity.clone(), type_id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Mesh, Transform};
+
+    #[test]
+    fn two_mutations_give_two_increasing_versions() {
+        let mut storage = ComponentStorage::new();
+        let entity = storage.spawn((Transform::default(), Mesh::default()));
+        let inserted = storage.component_version::<Transform>(&entity).unwrap();
+        storage
+            .get_entity_component_mut::<Transform>(&entity)
+            .unwrap()
+            .x = 1.0;
+        let first = storage.component_version::<Transform>(&entity).unwrap();
+        storage
+            .get_entity_component_mut::<Transform>(&entity)
+            .unwrap()
+            .x = 2.0;
+        let second = storage.component_version::<Transform>(&entity).unwrap();
+        assert!(inserted < first && first < second);
+        assert_eq!(storage.next_change_tick(), second + 1);
+
+        // Reads leave it alone, and it goes with the component
+        storage.get_entity_component::<Transform>(&entity).unwrap();
+        assert_eq!(
+            storage.component_version::<Transform>(&entity),
+            Some(second)
+        );
+        assert!(storage.component_version::<Mesh>(&entity).unwrap() < first);
+        storage.remove_component::<Transform>(&entity);
+        assert_eq!(storage.component_version::<Transform>(&entity), None);
+    }
+
+    #[test]
+    fn every_mutable_accessor_bumps_the_version() {
+        let mut storage = ComponentStorage::new();
+        let a = storage.spawn((Transform::default(), Mesh::default()));
+        let b = storage.spawn((Transform::default(), Mesh::default()));
+        let version = |storage: &ComponentStorage, entity| {
+            storage.component_version::<Transform>(entity).unwrap()
+        };
+
+        let before = [version(&storage, &a), version(&storage, &b)];
+        let mesh = storage.component_version::<Mesh>(&a);
+        for (_, mut transform, _) in storage.join_mut::<Transform, Mesh>() {
+            transform.x += 1.0;
+        }
+        assert!(version(&storage, &a) > before[0] && version(&storage, &b) > before[1]);
+        // Reading the other side of the join isn't a write
+        assert_eq!(storage.component_version::<Mesh>(&a), mesh);
+
+        let before = version(&storage, &a);
+        storage.take_component::<Transform>(&a);
+        let taken = version(&storage, &a);
+        storage.raw_component_ptr_mut::<Transform>(&a);
+        let raw = version(&storage, &a);
+        storage.raw_component_ptr::<Transform>(&a);
+        assert!(before < taken && taken < raw);
+        assert_eq!(version(&storage, &a), raw);
+
+        let before = version(&storage, &b);
+        storage.chunks_mut::<Transform>(2);
+        let chunked = version(&storage, &b);
+        storage.split_columns_mut::<Transform, Mesh>().unwrap();
+        assert!(before < chunked && chunked < version(&storage, &b));
+        assert!(storage.component_version::<Mesh>(&b).unwrap() > before);
+
+        // The insert takes `inserted`, so anything later came from `get_mut`
+        let inserted = storage.next_change_tick();
+        let spawned = storage.spawn_with_handle((Transform::default(),));
+        spawned.get_mut::<Transform>().unwrap().x = 1.0;
+        let entity = spawned.entity();
+        assert!(version(&storage, &entity) > inserted);
+    }
+}
@@ -0,0 +1,75 @@
+use alloc::{vec, vec::Vec};
+use core::any::Any;
+
+use crate::component::{ComponentStorage, Entity};
+
+pub trait ComponentVisitor {
+    fn visit(&mut self, entity: Entity, type_name: &str, component: &dyn Any);
+}
+
+impl ComponentStorage {
+    // Column by column, within a column in ascending entity id order. A shared slot is visited
+    // once per entity holding it. Blobs from `register_dyn_component` are not visited
+    pub fn visit_all(&self, visitor: &mut dyn ComponentVisitor) {
+        for component_vec in self.component_vectors.iter() {
+            let type_id = component_vec.component_type_id();
+            let mut owners = vec![Vec::new(); component_vec.len()];
+            for (id, table) in self.component_table.iter().enumerate() {
+                if let Some(&slot) = table.as_ref().and_then(|table| table.get(&type_id)) {
+                    owners[slot as usize].push(Entity(id as u32));
+                }
+            }
+            component_vec.visit_components(self, &owners, visitor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Mesh, Transform, Velocity};
+    use alloc::string::String;
+
+    #[derive(Default)]
+    struct Tally {
+        visited: usize,
+        transforms_x: f32,
+        by_entity: Vec<(Entity, String)>,
+    }
+
+    impl ComponentVisitor for Tally {
+        fn visit(&mut self, entity: Entity, type_name: &str, component: &dyn Any) {
+            self.visited += 1;
+            if let Some(transform) = component.downcast_ref::<Transform>() {
+                self.transforms_x += transform.x;
+            }
+            self.by_entity.push((entity, type_name.into()));
+        }
+    }
+
+    #[test]
+    fn every_component_of_every_entity_is_visited() {
+        let mut storage = ComponentStorage::new();
+        let a = storage.spawn((Transform { x: 1.0 }, Mesh::default()));
+        storage.spawn((Transform { x: 2.0 },));
+        let dead = storage.spawn((Transform { x: 4.0 }, Velocity::default()));
+        let b = storage.spawn((Velocity::default(),));
+        storage.share_component::<Mesh>(&a, &b);
+        storage.remove_entity(dead);
+
+        let mut tally = Tally::default();
+        storage.visit_all(&mut tally);
+        // a: 2, second: 1, b: its own Velocity plus the shared Mesh
+        assert_eq!(tally.visited, 5);
+        assert_eq!(tally.transforms_x, 3.0);
+        assert_eq!(
+            tally
+                .by_entity
+                .iter()
+                .filter(|(_, name)| name.ends_with("Mesh"))
+                .map(|(entity, _)| entity.clone())
+                .collect::<Vec<_>>(),
+            [a, b]
+        );
+    }
+}
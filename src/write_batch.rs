@@ -0,0 +1,142 @@
+use alloc::vec::Vec;
+use core::any::TypeId;
+
+use crate::component::{Component, ComponentStorage, Entity};
+
+// Buffers whole-value writes so they can be applied with the column borrowed once
+pub struct WriteBatch<T> {
+    writes: Vec<(Entity, T)>,
+}
+
+impl<T> Default for WriteBatch<T> {
+    fn default() -> Self {
+        Self { writes: Vec::new() }
+    }
+}
+
+impl<T: Component + 'static> WriteBatch<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            writes: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn push(&mut self, entity: Entity, component: T) {
+        self.writes.push((entity, component));
+    }
+
+    pub fn len(&self) -> usize {
+        self.writes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    // Writes land in the order they were pushed, entities without a `T` are skipped. An entity
+    // sharing its slot is given a slot of its own instead of writing to every sharer. Like
+    // `insert_component`, every written component is set up again on the next setup pass.
+    // Returns how many writes were applied
+    pub fn flush(&mut self, storage: &mut ComponentStorage) -> usize {
+        let mut applied = 0;
+        let mut in_place = Vec::with_capacity(self.writes.len());
+        for (entity, component) in self.writes.drain(..) {
+            match storage.get_entity_component_id::<T>(&entity) {
+                Some(slot) if storage.is_shared_slot::<T>(slot) => {
+                    applied += usize::from(storage.insert_component(&entity, component).is_some());
+                }
+                Some(slot) => in_place.push((entity, slot, component)),
+                None => {}
+            }
+        }
+        let Some(component_vec) = storage.get_component_vec_mut::<T>() else {
+            return applied;
+        };
+
+        let mut written = Vec::with_capacity(in_place.len());
+        for (entity, slot, component) in in_place {
+            if let Some(cell) = component_vec.get_mut(slot as usize) {
+                *cell.get_mut() = component;
+                written.push((entity, slot));
+            }
+        }
+        for (entity, _) in written.iter() {
+            storage.bump_version(entity, TypeId::of::<T>());
+        }
+        let slots: Vec<u32> = written.iter().map(|&(_, slot)| slot).collect();
+        storage.reset_set_up::<T>(&slots);
+        applied + written.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Mesh, SetupCount, Transform};
+
+    #[test]
+    fn flush_gives_a_shared_slot_its_own_copy() {
+        let mut storage = ComponentStorage::new();
+        let a = storage.spawn((Mesh { label: "a".into() },));
+        let b = storage.create_entity();
+        storage.share_component::<Mesh>(&a, &b);
+
+        let mut batch = WriteBatch::new();
+        batch.push(b.clone(), Mesh { label: "b".into() });
+        batch.push(b.clone(), Mesh { label: "c".into() });
+        assert_eq!(batch.flush(&mut storage), 2);
+        assert_eq!(storage.get_entity_component::<Mesh>(&a).unwrap().label, "a");
+        assert_eq!(storage.get_entity_component::<Mesh>(&b).unwrap().label, "c");
+        assert_eq!(storage.validate_integrity(), Ok(()));
+    }
+
+    #[test]
+    fn flush_applies_1000_buffered_transform_updates() {
+        let mut storage = ComponentStorage::new();
+        let entities = storage.spawn_batch(1000, |_| (Transform::default(), SetupCount::default()));
+        storage.setup_components();
+        let bare = storage.create_entity();
+        let mut batch = WriteBatch::with_capacity(1001);
+        for (i, entity) in entities.iter().enumerate() {
+            batch.push(entity.clone(), Transform { x: i as f32 });
+        }
+        batch.push(bare.clone(), Transform { x: -1.0 });
+        assert_eq!(batch.len(), 1001);
+        let before = storage
+            .component_version::<Transform>(&entities[0])
+            .unwrap();
+
+        assert_eq!(batch.flush(&mut storage), 1000);
+        assert!(batch.is_empty());
+        for (i, entity) in entities.iter().enumerate() {
+            assert_eq!(
+                storage.get_entity_component::<Transform>(entity).unwrap().x,
+                i as f32
+            );
+        }
+        assert!(
+            storage
+                .component_version::<Transform>(&entities[0])
+                .unwrap()
+                > before
+        );
+        assert!(storage.get_entity_component::<Transform>(&bare).is_none());
+
+        // An overwritten component is set up again, the others aren't
+        let mut batch = WriteBatch::new();
+        batch.push(entities[0].clone(), SetupCount { n: 10 });
+        batch.flush(&mut storage);
+        storage.setup_components();
+        let setups = |entity| {
+            storage
+                .get_entity_component::<SetupCount>(entity)
+                .unwrap()
+                .n
+        };
+        assert_eq!((setups(&entities[0]), setups(&entities[1])), (11, 1));
+    }
+}